@@ -0,0 +1,62 @@
+// 比较普通文件的三条统计路径：内存映射 (默认)、8KB 缓冲区的流式读取 (--no-mmap)
+// 和多线程分块统计 (--no-mmap 且 --jobs 大于 1，每个线程流式读取自己的一段)。
+//
+// 语料在运行时生成到临时文件中：
+// - log：ASCII 日志行，短行多，换行符密集；
+// - cjk：中文文本，多字节字符多，行较长；
+// - dense：每行只有一个数字，一半的字节是换行符。
+// 每种语料分别测试只统计行数 (向量化扫描) 和默认的 行/词/字节 两种情况。
+//
+// 运行：cargo bench --bench count
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rwc::Counter;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const CORPUS_SIZE: usize = 16 << 20; // 16 MiB
+const JOBS: usize = 4; // 分块统计的线程数
+
+fn generate(line: impl Fn(usize) -> String) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    let mut written = 0;
+    let mut i = 0;
+    while written < CORPUS_SIZE {
+        let line = line(i);
+        file.write_all(line.as_bytes()).unwrap();
+        written += line.len();
+        i += 1;
+    }
+    file.flush().unwrap();
+    file
+}
+
+fn bench_count(c: &mut Criterion) {
+    let corpora = [
+        ("log", generate(|i| format!("2024-01-01T00:00:{:02} INFO request id={} status=200 path=/api/items\n", i % 60, i))),
+        ("cjk", generate(|i| format!("第{}段：锈是一种注重安全、速度和并发的系统编程语言，所有权规则在编译时检查。\n", i))),
+        ("dense", generate(|i| format!("{}\n", i % 10))),
+    ];
+    let counters = [
+        ("lines", Counter::new().lines(true)),
+        ("default", Counter::new().lines(true).words(true).bytes(true)),
+    ];
+
+    for (corpus, file) in &corpora {
+        let mut group = c.benchmark_group(*corpus);
+        group.throughput(Throughput::Bytes(CORPUS_SIZE as u64));
+        group.sample_size(20);
+        for (name, counter) in &counters {
+            for (path, mmap, jobs) in [("mmap", true, 1), ("stream", false, 1), ("chunked", false, JOBS)] {
+                let counter = counter.clone().mmap(mmap).jobs(jobs);
+                group.bench_function(BenchmarkId::new(*name, path), |b| {
+                    b.iter(|| counter.count_file(file.path()).unwrap())
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_count);
+criterion_main!(benches);
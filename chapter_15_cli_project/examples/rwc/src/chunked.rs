@@ -0,0 +1,137 @@
+// 单个大文件的并行分块统计。
+//
+// 把文件按字节范围切成若干段，每段在独立的线程上用自己的文件句柄和 `StreamCounter` 统计，
+// 得到的 `PartialStats` 按段的顺序合并，结果与顺序统计完全相同。
+// 切分点会向后移动到第一个非 UTF-8 延续字节处，保证没有字符或无效序列横跨两段；
+// 跨段的单词和末行由 `PartialStats::merge` 修正。
+
+use crate::counter::{PartialStats, StreamCounter};
+use crate::parallel;
+use crate::Stats;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+
+// 每段至少 1 MiB，小文件分段的线程开销得不偿失
+pub const MIN_CHUNK_SIZE: u64 = 1 << 20;
+
+// 文件是否大到值得分块并行统计
+pub fn worth_chunking(len: u64, jobs: usize) -> bool {
+    jobs > 1 && len >= 2 * MIN_CHUNK_SIZE
+}
+
+// 用最多 `jobs` 个线程分块统计 `path` 指向的普通文件，行结束符为 `terminator`
+pub fn count_file_chunked(path: &Path, len: u64, jobs: usize, terminator: u8) -> io::Result<Stats> {
+    let chunks = jobs.min((len / MIN_CHUNK_SIZE) as usize).max(1);
+    count_file_in_chunks(path, len, chunks, jobs, terminator)
+}
+
+fn count_file_in_chunks(path: &Path, len: u64, chunks: usize, jobs: usize, terminator: u8) -> io::Result<Stats> {
+    let ranges = {
+        let mut file = File::open(path)?;
+        chunk_ranges(&mut file, len, chunks)?
+    };
+
+    let mut merged = PartialStats::default();
+    parallel::for_each_ordered(
+        &ranges,
+        jobs,
+        |range| count_range(path, range.clone(), terminator),
+        |_, partial: io::Result<PartialStats>| -> io::Result<()> {
+            merged = merged.merge(partial?);
+            Ok(())
+        },
+    )?;
+    Ok(merged.finish())
+}
+
+// 把 [0, len) 切成最多 `chunks` 段，每个切分点都向后对齐到非延续字节
+fn chunk_ranges(file: &mut File, len: u64, chunks: usize) -> io::Result<Vec<Range<u64>>> {
+    let mut ranges = Vec::with_capacity(chunks);
+    let mut start = 0;
+    for i in 1..=chunks as u64 {
+        let nominal_end = len * i / chunks as u64;
+        let end = if i == chunks as u64 { len } else { align_to_char_start(file, nominal_end.max(start), len)? };
+        if end > start {
+            ranges.push(start..end);
+            start = end;
+        }
+    }
+    Ok(ranges)
+}
+
+// 从 `offset` 开始找到第一个不是 UTF-8 延续字节的位置 (找不到时返回 `len`)
+fn align_to_char_start(file: &mut File, offset: u64, len: u64) -> io::Result<u64> {
+    let mut buf = [0u8; 64];
+    let mut pos = offset;
+    file.seek(SeekFrom::Start(pos))?;
+    while pos < len {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if let Some(i) = buf[..n].iter().position(|b| !(0x80..0xC0).contains(b)) {
+            return Ok(pos + i as u64);
+        }
+        pos += n as u64;
+    }
+    Ok(len)
+}
+
+fn count_range(path: &Path, range: Range<u64>, terminator: u8) -> io::Result<PartialStats> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(range.start))?;
+    let mut reader = file.take(range.end - range.start);
+    let mut byte_buffer = [0; 8192];
+    let mut counter = StreamCounter::with_terminator(terminator);
+    loop {
+        let n = crate::read_chunk(&mut reader, &mut byte_buffer)?;
+        if n == 0 {
+            break;
+        }
+        counter.update(&byte_buffer[..n]);
+    }
+    Ok(counter.finish_partial())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_chunked_matches_sequential() {
+        let mut content = Vec::new();
+        for i in 0..200 {
+            content.extend_from_slice(format!("第{}行 line {} 😊\u{3000}", i, i).as_bytes());
+            if i % 7 == 0 {
+                content.extend_from_slice(b"\xe4\xbd\x80\x80\xff"); // 截断的字符和孤立的延续字节
+            }
+            if i % 3 != 0 {
+                content.push(b'\n');
+            }
+        }
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        temp_file.write_all(&content).unwrap();
+        let len = content.len() as u64;
+
+        let mut counter = StreamCounter::default();
+        counter.update(&content);
+        let expected = counter.finish();
+
+        for chunks in [1, 2, 3, 7, 16, 64] {
+            let stats = count_file_in_chunks(temp_file.path(), len, chunks, 4, b'\n').unwrap();
+            assert_eq!(stats, expected, "chunks = {}", chunks);
+        }
+    }
+
+    #[test]
+    fn test_chunk_ranges_skip_continuation_bytes() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        temp_file.write_all("你好世界".as_bytes()).unwrap(); // 4 个 3 字节字符
+        let mut file = temp_file.reopen().unwrap();
+        let ranges = chunk_ranges(&mut file, 12, 5).unwrap();
+        assert_eq!(ranges, vec![0..3, 3..6, 6..9, 9..12]);
+    }
+}
@@ -0,0 +1,363 @@
+// --code-stats：按语言把每一行分为代码行、注释行和空行。
+//
+// 语言由文件扩展名决定 (Rust、C/C++、Python、Shell)，无法识别的文件不做分类。
+// 分类规则：
+// - 只包含空白字符的行是空行 (包括块注释和多行字符串中的空白行)；
+// - 含有任何代码 (包括字符串字面量中的内容) 的行是代码行，即使同一行还有注释；
+// - 其余的行 (只有注释) 是注释行。
+//
+// 块注释和多行字符串可以跨行，状态在行与行之间保留；
+// 字符串中的注释符号和注释中的引号不会被误认。Python 的文档字符串按字符串 (代码) 计。
+// 分类需要向前查看几个字节，所以逐行处理：只缓存当前这一行，内存占用与文件大小无关。
+
+use crate::Stats;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Language {
+    C,
+    Python,
+    Rust,
+    Shell,
+}
+
+impl Language {
+    // 根据扩展名识别语言
+    pub fn from_path(path: &Path) -> Option<Language> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Language::Rust),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(Language::C),
+            "py" | "pyi" | "pyw" => Some(Language::Python),
+            "sh" | "bash" | "zsh" | "ksh" => Some(Language::Shell),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::C => "C/C++",
+            Language::Python => "Python",
+            Language::Rust => "Rust",
+            Language::Shell => "Shell",
+        }
+    }
+
+    fn syntax(self) -> &'static Syntax {
+        match self {
+            Language::C => &C_SYNTAX,
+            Language::Python => &PYTHON_SYNTAX,
+            Language::Rust => &RUST_SYNTAX,
+            Language::Shell => &SHELL_SYNTAX,
+        }
+    }
+}
+
+// 字符串字面量的定界方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Quote {
+    open: &'static [u8],
+    close: &'static [u8],
+    escapes: bool,   // 反斜杠是否转义下一个字节
+    multiline: bool, // 是否可以不用续行符跨行
+}
+
+struct Syntax {
+    line_comments: &'static [&'static [u8]],
+    block_comment: Option<(&'static [u8], &'static [u8])>,
+    nested_comments: bool,
+    quotes: &'static [Quote], // 按顺序尝试，较长的开头 (如 """) 排在前面
+}
+
+const fn quote(open: &'static [u8], escapes: bool, multiline: bool) -> Quote {
+    Quote { open, close: open, escapes, multiline }
+}
+
+const RUST_STRING: Quote = quote(b"\"", true, true);
+const RUST_CHAR: Quote = quote(b"'", true, false);
+
+static RUST_SYNTAX: Syntax = Syntax {
+    line_comments: &[b"//"],
+    block_comment: Some((b"/*", b"*/")),
+    nested_comments: true,
+    quotes: &[RUST_STRING], // 原始字符串和字符字面量需要特殊判断，见 string_start
+};
+
+static C_SYNTAX: Syntax = Syntax {
+    line_comments: &[b"//"],
+    block_comment: Some((b"/*", b"*/")),
+    nested_comments: false,
+    quotes: &[quote(b"\"", true, false), quote(b"'", true, false)],
+};
+
+static PYTHON_SYNTAX: Syntax = Syntax {
+    line_comments: &[b"#"],
+    block_comment: None,
+    nested_comments: false,
+    quotes: &[
+        quote(b"\"\"\"", true, true),
+        quote(b"'''", true, true),
+        quote(b"\"", true, false),
+        quote(b"'", true, false),
+    ],
+};
+
+static SHELL_SYNTAX: Syntax = Syntax {
+    line_comments: &[b"#"],
+    block_comment: None,
+    nested_comments: false,
+    quotes: &[quote(b"\"", true, true), quote(b"'", false, true)],
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Normal,
+    BlockComment(usize), // 嵌套深度
+    Str(Quote),
+    RawStr(usize), // Rust 原始字符串，记录 # 的个数
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Code,
+    Comment,
+    Blank,
+}
+
+// 流式的行分类器：与 StreamCounter 一样接收任意切分的字节块
+#[derive(Debug, Clone)]
+pub struct CodeCounter {
+    language: Language,
+    state: State,
+    line: Vec<u8>, // 当前尚未结束的行
+    code: usize,
+    comment: usize,
+    blank: usize,
+}
+
+impl CodeCounter {
+    pub fn new(language: Language) -> Self {
+        CodeCounter { language, state: State::Normal, line: Vec::new(), code: 0, comment: 0, blank: 0 }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        let mut rest = bytes;
+        while let Some(pos) = rest.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&rest[..pos]);
+            self.end_line();
+            rest = &rest[pos + 1..];
+        }
+        self.line.extend_from_slice(rest);
+    }
+
+    // 把分类结果写入 `stats`；末尾没有换行符的最后一行也计入
+    pub fn finish(mut self, stats: &mut Stats) {
+        if !self.line.is_empty() {
+            self.end_line();
+        }
+        stats.code_lines = self.code;
+        stats.comment_lines = self.comment;
+        stats.blank_lines = self.blank;
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        match self.classify(&line) {
+            LineKind::Code => self.code += 1,
+            LineKind::Comment => self.comment += 1,
+            LineKind::Blank => self.blank += 1,
+        }
+        self.line = line;
+        self.line.clear(); // 复用缓冲区
+    }
+
+    fn classify(&mut self, line: &[u8]) -> LineKind {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return LineKind::Blank;
+        }
+        let syntax = self.language.syntax();
+        let (mut code, mut comment) = (false, false);
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            match self.state {
+                State::BlockComment(depth) => {
+                    comment = true;
+                    let (open, close) = syntax.block_comment.expect("只有支持块注释的语言才会进入该状态");
+                    if rest.starts_with(close) {
+                        self.state = if depth > 1 { State::BlockComment(depth - 1) } else { State::Normal };
+                        i += close.len();
+                    } else if syntax.nested_comments && rest.starts_with(open) {
+                        self.state = State::BlockComment(depth + 1);
+                        i += open.len();
+                    } else {
+                        i += 1;
+                    }
+                }
+                State::Str(quote) => {
+                    code = true;
+                    if quote.escapes && rest[0] == b'\\' {
+                        i += 2;
+                    } else if rest.starts_with(quote.close) {
+                        self.state = State::Normal;
+                        i += quote.close.len();
+                    } else {
+                        i += 1;
+                    }
+                }
+                State::RawStr(hashes) => {
+                    code = true;
+                    if rest[0] == b'"' && rest[1..].iter().take_while(|&&b| b == b'#').count() >= hashes {
+                        self.state = State::Normal;
+                        i += 1 + hashes;
+                    } else {
+                        i += 1;
+                    }
+                }
+                State::Normal => {
+                    if rest[0].is_ascii_whitespace() {
+                        i += 1;
+                    } else if self.is_line_comment(line, i) {
+                        comment = true;
+                        break;
+                    } else if let Some((open, _)) = syntax.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+                        self.state = State::BlockComment(1);
+                        i += open.len();
+                    } else {
+                        code = true;
+                        match self.string_start(line, i) {
+                            Some((state, len)) => {
+                                self.state = state;
+                                i += len;
+                            }
+                            None => i += 1,
+                        }
+                    }
+                }
+            }
+        }
+
+        // 不能跨行的字符串在行尾结束，除非以反斜杠续行
+        if let State::Str(quote) = self.state {
+            if !quote.multiline && line.last() != Some(&b'\\') {
+                self.state = State::Normal;
+            }
+        }
+
+        if code {
+            LineKind::Code
+        } else if comment {
+            LineKind::Comment
+        } else {
+            LineKind::Blank
+        }
+    }
+
+    fn is_line_comment(&self, line: &[u8], i: usize) -> bool {
+        let syntax = self.language.syntax();
+        if !syntax.line_comments.iter().any(|prefix| line[i..].starts_with(prefix)) {
+            return false;
+        }
+        // Shell 中只有位于单词开头的 # 才是注释 (排除 $#、${#var} 等)
+        self.language != Language::Shell || i == 0 || line[i - 1].is_ascii_whitespace()
+    }
+
+    // 如果 line[i..] 是一个字符串字面量的开头，返回进入的状态和开头的长度
+    fn string_start(&self, line: &[u8], i: usize) -> Option<(State, usize)> {
+        let rest = &line[i..];
+        if self.language == Language::Rust {
+            let after_ident = i > 0 && (line[i - 1].is_ascii_alphanumeric() || line[i - 1] == b'_');
+            // r"..."、r#"..."#、br"..."
+            let prefix = if rest.starts_with(b"br") { 2 } else if rest[0] == b'r' { 1 } else { 0 };
+            if prefix > 0 && !after_ident {
+                let hashes = rest[prefix..].iter().take_while(|&&b| b == b'#').count();
+                if rest.get(prefix + hashes) == Some(&b'"') {
+                    return Some((State::RawStr(hashes), prefix + hashes + 1));
+                }
+            }
+            // 'x' 和 '\n' 是字符字面量，'a 是生命周期
+            if rest[0] == b'\'' {
+                let is_char = rest.get(1) == Some(&b'\\') || char_literal_len(&rest[1..]).is_some();
+                return is_char.then_some((State::Str(RUST_CHAR), 1));
+            }
+        }
+        self.language
+            .syntax()
+            .quotes
+            .iter()
+            .find(|quote| rest.starts_with(quote.open))
+            .map(|&quote| (State::Str(quote), quote.open.len()))
+    }
+}
+
+// 如果 `rest` 以一个 UTF-8 字符和闭合的单引号开头，返回该字符的长度
+fn char_literal_len(rest: &[u8]) -> Option<usize> {
+    let len = match rest.first()? {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    };
+    (rest.get(len) == Some(&b'\'')).then_some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(language: Language, source: &str) -> (usize, usize, usize) {
+        let mut counter = CodeCounter::new(language);
+        // 逐字节输入，确保跨块的行也能正确处理
+        for byte in source.as_bytes() {
+            counter.update(std::slice::from_ref(byte));
+        }
+        let mut stats = Stats::default();
+        counter.finish(&mut stats);
+        (stats.code_lines, stats.comment_lines, stats.blank_lines)
+    }
+
+    #[test]
+    fn test_language_from_path() {
+        assert_eq!(Language::from_path(Path::new("src/main.rs")), Some(Language::Rust));
+        assert_eq!(Language::from_path(Path::new("a.HPP")), Some(Language::C));
+        assert_eq!(Language::from_path(Path::new("x.py")), Some(Language::Python));
+        assert_eq!(Language::from_path(Path::new("run.sh")), Some(Language::Shell));
+        assert_eq!(Language::from_path(Path::new("README.md")), None);
+        assert_eq!(Language::from_path(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_rust() {
+        let source = r##"// 行注释
+/* 块注释 /* 嵌套 */
+   仍然是注释 */
+
+fn main() { // 行尾注释
+    let s = "// 不是注释
+    /* 也不是 */";
+    let r = r#"包含 " 和 /* 的原始字符串"#;
+    let c = '"'; let l: &'static str = "x";
+    /* 注释 */ let x = 1;
+}"##;
+        // 代码：fn main、let s、/* 也不是 */ (字符串内)、let r、let c、/* 注释 */ let x、}
+        assert_eq!(classify(Language::Rust, source), (7, 3, 1));
+    }
+
+    #[test]
+    fn test_c() {
+        let source = "#include <stdio.h>\n/* 不嵌套 /* 注释\n*/\nint main() {\n    printf(\"/* %c */\", '\"'); // 输出\n\n    return 0; /* 返回 */\n}\n";
+        assert_eq!(classify(Language::C, source), (5, 2, 1));
+    }
+
+    #[test]
+    fn test_python() {
+        let source = "#!/usr/bin/env python3\n# 注释\ndef f():\n    \"\"\"文档字符串\n\n    # 不是注释\n    \"\"\"\n    return '#' # 注释\n";
+        assert_eq!(classify(Language::Python, source), (5, 2, 1));
+    }
+
+    #[test]
+    fn test_shell() {
+        let source = "#!/bin/sh\n# 注释\necho \"$# 个参数 # 不是注释\"\necho ${#var} # 注释\nmsg='多行\n# 字符串'\n\n  \t\n";
+        assert_eq!(classify(Language::Shell, source), (4, 2, 2));
+    }
+}
@@ -0,0 +1,302 @@
+// 配置文件和环境变量提供的默认设置。
+//
+// 优先级从高到低：命令行参数、环境变量、配置文件、内置默认值。
+// - 配置文件：$RWC_CONFIG 指定的文件，否则为 $XDG_CONFIG_HOME/rwc/config.toml
+//   (未设置 XDG_CONFIG_HOME 时为 ~/.config/rwc/config.toml)，默认位置的文件不存在时忽略；
+// - 环境变量：RWC_METRICS (逗号分隔)、RWC_FORMAT、RWC_WIDTH，取值与配置文件相同。
+//
+// 配置文件示例：
+//
+//     metrics = ["lines", "words", "chars"]  # 命令行没有选择任何统计项时统计的项
+//     format = "json"                         # 输出格式
+//     width = 8                               # 表格的列宽，"auto" 表示按 GNU wc 的规则计算
+//
+// 统计项的名称与对应的长选项相同。命令行上选择了任何统计项时，配置的统计项不起作用。
+// `--print-config` 输出合并之后的有效设置及其来源，输出本身也是合法的配置文件。
+
+use crate::output::Format;
+use crate::{Cli, Command};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::Deserialize;
+use std::fmt;
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// 可以在配置中选择的统计项
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Metric {
+    Lines,
+    Words,
+    Bytes,
+    Chars,
+    Graphemes,
+    MaxLineLength,
+    Invalid,
+}
+
+impl Metric {
+    const ALL: [Metric; 7] = [
+        Metric::Lines,
+        Metric::Words,
+        Metric::Bytes,
+        Metric::Chars,
+        Metric::Graphemes,
+        Metric::MaxLineLength,
+        Metric::Invalid,
+    ];
+
+    fn is_selected(self, cli: &Cli) -> bool {
+        match self {
+            Metric::Lines => cli.lines,
+            Metric::Words => cli.words,
+            Metric::Bytes => cli.bytes,
+            Metric::Chars => cli.chars,
+            Metric::Graphemes => cli.graphemes,
+            Metric::MaxLineLength => cli.max_line_length,
+            Metric::Invalid => cli.invalid,
+        }
+    }
+
+    fn select(self, cli: &mut Cli) {
+        match self {
+            Metric::Lines => cli.lines = true,
+            Metric::Words => cli.words = true,
+            Metric::Bytes => cli.bytes = true,
+            Metric::Chars => cli.chars = true,
+            Metric::Graphemes => cli.graphemes = true,
+            Metric::MaxLineLength => cli.max_line_length = true,
+            Metric::Invalid => cli.invalid = true,
+        }
+    }
+
+    fn name(self) -> String {
+        self.to_possible_value().expect("没有跳过的取值").get_name().to_string()
+    }
+}
+
+// 表格的列宽：`auto` 按 GNU wc 的规则计算，或者固定的最小宽度
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "RawWidth")]
+pub enum Width {
+    #[default]
+    Auto,
+    Fixed(NonZeroUsize),
+}
+
+impl FromStr for Width {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(Width::Auto),
+            _ => value.parse().map(Width::Fixed).map_err(|_| format!("无效的列宽 '{}' (应为正整数或 auto)", value)),
+        }
+    }
+}
+
+impl fmt::Display for Width {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Width::Auto => write!(f, "\"auto\""),
+            Width::Fixed(width) => write!(f, "{}", width),
+        }
+    }
+}
+
+// 配置文件中的列宽可以是整数或字符串 "auto"
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawWidth {
+    Number(usize),
+    Text(String),
+}
+
+impl TryFrom<RawWidth> for Width {
+    type Error = String;
+
+    fn try_from(raw: RawWidth) -> Result<Self, Self::Error> {
+        match raw {
+            RawWidth::Number(width) => NonZeroUsize::new(width).map(Width::Fixed).ok_or_else(|| "列宽必须大于 0".to_string()),
+            RawWidth::Text(text) => text.parse(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    metrics: Option<Vec<Metric>>,
+    format: Option<Format>,
+    width: Option<Width>,
+}
+
+// 一项设置的来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    Default,
+    File(PathBuf),
+    Env(&'static str),
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "默认值"),
+            Source::File(path) => write!(f, "配置文件 {}", path.display()),
+            Source::Env(name) => write!(f, "环境变量 {}", name),
+            Source::CommandLine => write!(f, "命令行"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Config {
+    metrics: Option<(Vec<Metric>, Source)>,
+    format: Option<(Format, Source)>,
+    width: Option<(Width, Source)>,
+}
+
+impl Config {
+    // 读取配置文件和环境变量；出错时返回说明出错位置的信息
+    pub fn load() -> Result<Config, String> {
+        let lookup = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let file = match lookup("RWC_CONFIG") {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                let text = std::fs::read_to_string(&path).map_err(|e| format!("无法读取配置文件 {}: {}", path.display(), e))?;
+                Some((path, text))
+            }
+            None => default_path().and_then(|path| Some((path.clone(), std::fs::read_to_string(path).ok()?))),
+        };
+        Config::from_sources(file, lookup)
+    }
+
+    fn from_sources(file: Option<(PathBuf, String)>, env: impl Fn(&str) -> Option<String>) -> Result<Config, String> {
+        let mut config = Config { metrics: None, format: None, width: None };
+        if let Some((path, text)) = file {
+            let parsed: ConfigFile = toml::from_str(&text).map_err(|e| format!("配置文件 {} 有误: {}", path.display(), e))?;
+            let source = Source::File(path);
+            config.metrics = parsed.metrics.map(|metrics| (metrics, source.clone()));
+            config.format = parsed.format.map(|format| (format, source.clone()));
+            config.width = parsed.width.map(|width| (width, source));
+        }
+
+        let invalid = |name: &str, message: String| format!("环境变量 {} 有误: {}", name, message);
+        if let Some(value) = env("RWC_METRICS") {
+            let metrics = value
+                .split(',')
+                .map(|name| Metric::from_str(name.trim(), false))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| invalid("RWC_METRICS", e))?;
+            config.metrics = Some((metrics, Source::Env("RWC_METRICS")));
+        }
+        if let Some(value) = env("RWC_FORMAT") {
+            let format = Format::from_str(&value, false).map_err(|e| invalid("RWC_FORMAT", e))?;
+            config.format = Some((format, Source::Env("RWC_FORMAT")));
+        }
+        if let Some(value) = env("RWC_WIDTH") {
+            let width = value.parse().map_err(|e| invalid("RWC_WIDTH", e))?;
+            config.width = Some((width, Source::Env("RWC_WIDTH")));
+        }
+        Ok(config)
+    }
+
+    // 把配置合并到命令行参数中：只填补命令行上没有给出的设置
+    pub fn apply(&self, cli: &mut Cli, matches: &ArgMatches) {
+        let metrics_given = Metric::ALL.into_iter().any(|metric| metric.is_selected(cli));
+        if let (false, Some((metrics, _))) = (metrics_given, &self.metrics) {
+            for metric in metrics {
+                metric.select(cli);
+            }
+        }
+        if let Some((format, _)) = self.format {
+            if matches.value_source("format") != Some(ValueSource::CommandLine) {
+                cli.format = format;
+            }
+            // diff 子命令有自己的 --format
+            if let (Some(Command::Diff(args)), Some(("diff", diff_matches))) = (&mut cli.command, matches.subcommand()) {
+                if diff_matches.value_source("format") != Some(ValueSource::CommandLine) {
+                    args.format = format;
+                }
+            }
+        }
+        if let (None, Some((width, _))) = (cli.width, &self.width) {
+            cli.width = Some(*width);
+        }
+    }
+
+    // --print-config：输出合并之后的有效设置，每项注明来源。`cli` 是合并之前的命令行参数
+    pub fn print<W: Write>(&self, mut out: W, cli: &Cli, matches: &ArgMatches) -> io::Result<()> {
+        let given: Vec<Metric> = Metric::ALL.into_iter().filter(|metric| metric.is_selected(cli)).collect();
+        let (metrics, metrics_source) = match (&self.metrics, given.is_empty()) {
+            (_, false) => (given, Source::CommandLine),
+            (Some((metrics, source)), true) => (metrics.clone(), source.clone()),
+            (None, true) => (vec![Metric::Lines, Metric::Words, Metric::Bytes], Source::Default),
+        };
+        let (format, format_source) = match (&self.format, matches.value_source("format")) {
+            (_, Some(ValueSource::CommandLine)) => (cli.format, Source::CommandLine),
+            (Some((format, source)), _) => (*format, source.clone()),
+            (None, _) => (cli.format, Source::Default),
+        };
+        let (width, width_source) = match (cli.width, &self.width) {
+            (Some(width), _) => (width, Source::CommandLine),
+            (None, Some((width, source))) => (*width, source.clone()),
+            (None, None) => (Width::Auto, Source::Default),
+        };
+
+        let names: Vec<String> = metrics.iter().map(|metric| format!("\"{}\"", metric.name())).collect();
+        let format_name = format.to_possible_value().expect("没有跳过的取值").get_name().to_string();
+        writeln!(out, "metrics = [{}] # {}", names.join(", "), metrics_source)?;
+        writeln!(out, "format = \"{}\" # {}", format_name, format_source)?;
+        writeln!(out, "width = {} # {}", width, width_source)?;
+        out.flush()
+    }
+}
+
+// 默认的配置文件位置
+fn default_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("rwc").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(file: Option<&str>, env: &[(&str, &str)]) -> Result<Config, String> {
+        let file = file.map(|text| (PathBuf::from("config.toml"), text.to_string()));
+        Config::from_sources(file, |name| env.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string()))
+    }
+
+    #[test]
+    fn test_file_and_env_precedence() {
+        let file = "metrics = [\"lines\", \"max-line-length\"]\nformat = \"csv\"\nwidth = 8\n";
+        let config = load(Some(file), &[]).unwrap();
+        assert_eq!(config.metrics.unwrap().0, [Metric::Lines, Metric::MaxLineLength]);
+        assert_eq!(config.width.unwrap(), (Width::Fixed(NonZeroUsize::new(8).unwrap()), Source::File(PathBuf::from("config.toml"))));
+
+        // 环境变量优先于配置文件
+        let config = load(Some(file), &[("RWC_FORMAT", "json"), ("RWC_METRICS", "words, chars"), ("RWC_WIDTH", "auto")]).unwrap();
+        assert_eq!(config.format.unwrap(), (Format::Json, Source::Env("RWC_FORMAT")));
+        assert_eq!(config.metrics.unwrap().0, [Metric::Words, Metric::Chars]);
+        assert_eq!(config.width.unwrap().0, Width::Auto);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(load(Some("colour = \"red\"\n"), &[]).unwrap_err().contains("config.toml"));
+        assert!(load(Some("width = \"wide\"\n"), &[]).is_err());
+        assert!(load(None, &[("RWC_METRICS", "lines,pages")]).unwrap_err().starts_with("环境变量 RWC_METRICS 有误"));
+        assert!(load(None, &[("RWC_WIDTH", "-1")]).is_err());
+        assert!(load(Some("width = 0\n"), &[]).unwrap_err().contains("列宽必须大于 0"));
+        assert!(load(None, &[("RWC_WIDTH", "0")]).unwrap_err().starts_with("环境变量 RWC_WIDTH 有误"));
+    }
+}
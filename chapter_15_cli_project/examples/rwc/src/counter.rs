@@ -0,0 +1,509 @@
+// 流式统计状态机：一次遍历、固定大小缓冲区，同时统计行、词、字节、字符和最长行宽度。
+//
+// 调用方把任意切分的字节块依次交给 `update`，最后调用 `finish` 得到 `Stats`。
+// 被缓冲区边界截断的 UTF-8 多字节序列会暂存在 `pending` 中，等下一块数据到来后再拼接解码；
+// 单词状态 (`in_word`) 也跨块保留，所以横跨边界的单词和空白都能被正确处理。
+//
+// 各项计数的定义与 GNU wc (coreutils 9.x) 在 UTF-8 locale 下一致：
+// - 行数是 '\n' 的个数，末尾没有换行符的最后一行不计入；行结束符可以换成其他 ASCII 字节
+//   (例如 NUL 分隔的数据)，这时行数是该字节的个数，该字节也和空白一样分隔单词；
+// - 单词是由分隔符隔开的、至少包含一个可打印字符的序列。分隔符是 Unicode 空白字符
+//   (U+0085、U+2028、U+2029 除外) 加上 U+2060；不可打印的字符 (控制字符等) 既不开始
+//   也不结束一个单词；
+// - 最长行宽度 (-L) 按显示宽度计算：制表符跳到下一个 8 的倍数列，宽字符占 2 列，
+//   不可打印字符占 0 列，'\r'、'\x0c' 和行结束符与 '\n' 一样结束当前行的宽度计算。
+//
+// 无效 UTF-8 的处理策略 (同样与 GNU wc 一致)：
+// - 行数和字节数按原始字节统计，不受编码影响；
+// - 每个无效序列按 Unicode 的 "最大子部分" 规则划分 (与 `String::from_utf8_lossy`
+//   替换为 U+FFFD 的划分方式相同)，每个计入一次 `Stats::invalid`；
+// - 无效序列不计入字符数，宽度为 0，也不改变单词状态。
+//
+// 为了支持把一个文件切成多段并行统计，`finish_partial` 返回可合并的 `PartialStats`，
+// 其中记录了首尾的单词状态和首尾行的宽度信息，合并时据此修正跨段的单词和行宽度。
+// 只要切分点不落在 UTF-8 延续字节 (0x80..=0xBF) 上，就不会有字符或无效序列横跨两段。
+
+use crate::Stats;
+use unicode_width::UnicodeWidthChar;
+
+// UTF-8 编码的最大字节数
+const MAX_UTF8_LEN: usize = 4;
+
+// 制表符的宽度
+const TAB_WIDTH: usize = 8;
+
+// 字符对单词状态的影响
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Separator,   // 结束当前单词
+    Word,        // 开始或延续一个单词
+    Transparent, // 不可打印字符：不改变单词状态
+}
+
+fn classify(c: char) -> CharClass {
+    match c {
+        // glibc 不把这几个字符视为空白，而 GNU wc 额外把 U+2060 视为分隔符
+        '\u{85}' | '\u{2028}' | '\u{2029}' => CharClass::Transparent,
+        '\u{2060}' => CharClass::Separator,
+        c if c.is_whitespace() => CharClass::Separator,
+        c if c.is_control() => CharClass::Transparent,
+        _ => CharClass::Word,
+    }
+}
+
+fn next_tab_stop(column: usize) -> usize {
+    column + TAB_WIDTH - column % TAB_WIDTH
+}
+
+// 一段不含换行的文本对列位置的影响：从第 x 列开始，结束时位于 `end_column(x)` 列。
+// 没有制表符时是 x + advance；有制表符时，第一个制表符之后的列位置只取决于
+// 第一个制表符落在哪个制表位上，所以可以写成 next_tab_stop(x + advance) + after_tab。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct LineSegment {
+    advance: usize,           // 第一个制表符之前的宽度
+    after_tab: Option<usize>, // 第一个制表符之后的宽度 (相对于该制表位)
+}
+
+impl LineSegment {
+    fn push_width(&mut self, width: usize) {
+        match &mut self.after_tab {
+            None => self.advance += width,
+            Some(after_tab) => *after_tab += width,
+        }
+    }
+
+    fn push_tab(&mut self) {
+        self.after_tab = Some(self.after_tab.map_or(0, next_tab_stop));
+    }
+
+    fn end_column(self, start: usize) -> usize {
+        match self.after_tab {
+            None => start + self.advance,
+            Some(after_tab) => next_tab_stop(start + self.advance) + after_tab,
+        }
+    }
+
+    // 先经过 `self` 再经过 `next` 的组合效果
+    fn then(self, next: LineSegment) -> LineSegment {
+        match (self.after_tab, next.after_tab) {
+            (None, None) => LineSegment { advance: self.advance + next.advance, after_tab: None },
+            (None, Some(_)) => LineSegment { advance: self.advance + next.advance, ..next },
+            (Some(a), None) => LineSegment { after_tab: Some(a + next.advance), ..self },
+            (Some(a), Some(b)) => LineSegment {
+                after_tab: Some(next_tab_stop(a + next.advance) + b),
+                ..self
+            },
+        }
+    }
+}
+
+// 一段输入的统计结果，可以与相邻段的结果按顺序合并
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PartialStats {
+    stats: Stats,                 // 其中 max_line_length 尚未计算，由 finish 得出
+    starts_in_word: Option<bool>, // 第一个影响单词状态的字符是否属于单词 (None 表示没有这样的字符)
+    ends_in_word: Option<bool>,   // 最后一个影响单词状态的字符是否属于单词
+    head: LineSegment,            // 第一个行结束符之前的内容，其宽度取决于前一段结尾的列位置
+    line_break: bool,             // 是否包含结束行宽度计算的字符 ('\n'、'\r'、'\x0c' 或行结束符)
+    max_inner_line: usize,        // 第一个行结束符之后的完整行的最大宽度
+    tail_column: usize,           // 最后一个行结束符之后的列位置
+}
+
+impl PartialStats {
+    // 把紧跟在 `self` 之后的一段合并进来
+    pub fn merge(self, next: PartialStats) -> PartialStats {
+        // 前一段以单词结尾而后一段以单词开头：同一个单词被两段各计了一次
+        let split_word = self.ends_in_word == Some(true) && next.starts_in_word == Some(true);
+        let stats = Stats {
+            lines: self.stats.lines + next.stats.lines,
+            words: self.stats.words + next.stats.words - usize::from(split_word),
+            bytes: self.stats.bytes + next.stats.bytes,
+            chars: self.stats.chars + next.stats.chars,
+            max_line_length: 0,
+            invalid: self.stats.invalid + next.stats.invalid,
+            ..Stats::default() // 行分类 (--code-stats) 不分块统计
+        };
+        // 没有影响单词状态的字符的一段 (例如只有无效字节) 不改变单词状态
+        let starts_in_word = self.starts_in_word.or(next.starts_in_word);
+        let ends_in_word = next.ends_in_word.or(self.ends_in_word);
+
+        if !self.line_break {
+            // 前一段整体都是 next 第一行的开头部分
+            return PartialStats { stats, starts_in_word, ends_in_word, head: self.head.then(next.head), ..next };
+        }
+        // 前一段的最后一行与后一段的第一行拼接成一行
+        let joined = next.head.end_column(self.tail_column);
+        let (max_inner_line, tail_column) = if next.line_break {
+            (self.max_inner_line.max(joined).max(next.max_inner_line), next.tail_column)
+        } else {
+            (self.max_inner_line, joined)
+        };
+        PartialStats { stats, starts_in_word, ends_in_word, max_inner_line, tail_column, ..self }
+    }
+
+    // 所有段合并完毕后得到最终结果 (第一段从第 0 列开始)
+    pub fn finish(self) -> Stats {
+        let mut stats = self.stats;
+        let first_line = self.head.end_column(0);
+        stats.max_line_length = if self.line_break {
+            first_line.max(self.max_inner_line).max(self.tail_column)
+        } else {
+            first_line
+        };
+        stats
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamCounter {
+    partial: PartialStats,
+    terminator: u8, // 行结束符，必须是 ASCII 字节
+    in_word: bool, // 上一个影响单词状态的字符是否属于某个单词
+    // 被块边界截断、尚未解码完成的 UTF-8 序列
+    pending: [u8; MAX_UTF8_LEN],
+    pending_len: usize,
+}
+
+impl Default for StreamCounter {
+    fn default() -> Self {
+        StreamCounter::with_terminator(b'\n')
+    }
+}
+
+impl StreamCounter {
+    pub fn with_terminator(terminator: u8) -> Self {
+        debug_assert!(terminator.is_ascii(), "行结束符必须是 ASCII 字节");
+        StreamCounter {
+            partial: PartialStats::default(),
+            terminator,
+            in_word: false,
+            pending: [0; MAX_UTF8_LEN],
+            pending_len: 0,
+        }
+    }
+
+    // 处理下一块输入数据，块可以在任意字节处切分
+    pub fn update(&mut self, mut buf: &[u8]) {
+        self.partial.stats.bytes += buf.len();
+
+        if self.pending_len > 0 && !buf.is_empty() {
+            let consumed = self.complete_pending(buf);
+            buf = &buf[consumed..];
+        }
+
+        loop {
+            match std::str::from_utf8(buf) {
+                Ok(s) => {
+                    self.count_str(s);
+                    return;
+                }
+                Err(e) => {
+                    let (valid, rest) = buf.split_at(e.valid_up_to());
+                    // SAFETY: `valid_up_to` 之前的字节已由 from_utf8 验证为合法 UTF-8
+                    self.count_str(unsafe { std::str::from_utf8_unchecked(valid) });
+                    match e.error_len() {
+                        // 末尾是一个不完整的序列：留到下一块再解码
+                        None => {
+                            self.pending[..rest.len()].copy_from_slice(rest);
+                            self.pending_len = rest.len();
+                            return;
+                        }
+                        Some(invalid_len) => {
+                            self.partial.stats.invalid += 1;
+                            buf = &rest[invalid_len..];
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 输入结束，返回最终统计结果
+    pub fn finish(self) -> Stats {
+        self.finish_partial().finish()
+    }
+
+    // 输入结束，返回可与相邻段合并的统计结果
+    pub fn finish_partial(self) -> PartialStats {
+        let mut partial = self.partial;
+        if self.pending_len > 0 {
+            // 输入在一个多字节字符的中间结束，剩余字节构成一个无效序列
+            partial.stats.invalid += 1;
+        }
+        partial
+    }
+
+    // 用新块开头的字节补全上一块遗留的序列，返回从 `buf` 中消耗的字节数
+    fn complete_pending(&mut self, buf: &[u8]) -> usize {
+        let mut joined = [0u8; MAX_UTF8_LEN];
+        let pending_len = self.pending_len;
+        let take = buf.len().min(MAX_UTF8_LEN - pending_len);
+        joined[..pending_len].copy_from_slice(&self.pending[..pending_len]);
+        joined[pending_len..pending_len + take].copy_from_slice(&buf[..take]);
+        let joined = &joined[..pending_len + take];
+
+        let char_len = match std::str::from_utf8(joined) {
+            Ok(s) => s.chars().next().map_or(0, char::len_utf8),
+            Err(e) if e.valid_up_to() > 0 => {
+                // SAFETY: `valid_up_to` 之前的字节已由 from_utf8 验证为合法 UTF-8
+                let s = unsafe { std::str::from_utf8_unchecked(&joined[..e.valid_up_to()]) };
+                s.chars().next().map_or(0, char::len_utf8)
+            }
+            Err(e) => match e.error_len() {
+                // 仍然不完整 (新块太短)，全部并入 pending
+                None => {
+                    self.pending[pending_len..pending_len + take].copy_from_slice(&buf[..take]);
+                    self.pending_len += take;
+                    return take;
+                }
+                // pending 中的字节是某个序列的合法前缀，所以无效序列至少包含全部 pending 字节
+                Some(invalid_len) => {
+                    self.partial.stats.invalid += 1;
+                    self.pending_len = 0;
+                    return invalid_len - pending_len;
+                }
+            },
+        };
+
+        // SAFETY: 上面已确认 `joined` 的前 `char_len` 个字节是一个完整的合法字符
+        let s = unsafe { std::str::from_utf8_unchecked(&joined[..char_len]) };
+        self.count_str(s);
+        self.pending_len = 0;
+        char_len - pending_len
+    }
+
+    fn count_str(&mut self, s: &str) {
+        // 行结束符是 '\n' 时按常量编译，热循环中不需要额外的比较
+        if self.terminator == b'\n' {
+            self.count_str_with::<false>(s);
+        } else {
+            self.count_str_with::<true>(s);
+        }
+    }
+
+    fn count_str_with<const CUSTOM_TERMINATOR: bool>(&mut self, s: &str) {
+        let terminator = if CUSTOM_TERMINATOR { self.terminator } else { b'\n' };
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            let class = if b.is_ascii() {
+                // ASCII 快速路径
+                i += 1;
+                match b {
+                    _ if b == terminator => {
+                        self.partial.stats.lines += 1;
+                        self.end_line();
+                        CharClass::Separator
+                    }
+                    b'\n' | b'\r' | b'\x0c' => {
+                        self.end_line();
+                        CharClass::Separator
+                    }
+                    b'\t' => {
+                        self.push_tab();
+                        CharClass::Separator
+                    }
+                    b' ' => {
+                        self.push_width(1);
+                        CharClass::Separator
+                    }
+                    b'\x0b' => CharClass::Separator,
+                    0x21..=0x7e => {
+                        self.push_width(1);
+                        CharClass::Word
+                    }
+                    _ => CharClass::Transparent, // 其余控制字符
+                }
+            } else {
+                let c = s[i..].chars().next().expect("i 始终位于字符边界");
+                i += c.len_utf8();
+                let class = classify(c);
+                if class != CharClass::Transparent {
+                    self.push_width(c.width().unwrap_or(0));
+                }
+                class
+            };
+            self.partial.stats.chars += 1;
+            if class == CharClass::Transparent {
+                continue;
+            }
+            let is_word = class == CharClass::Word;
+            if is_word && !self.in_word {
+                self.partial.stats.words += 1;
+            }
+            self.in_word = is_word;
+            self.partial.starts_in_word.get_or_insert(is_word);
+            self.partial.ends_in_word = Some(is_word);
+        }
+    }
+
+    fn push_width(&mut self, width: usize) {
+        if self.partial.line_break {
+            self.partial.tail_column += width;
+        } else {
+            self.partial.head.push_width(width);
+        }
+    }
+
+    fn push_tab(&mut self) {
+        if self.partial.line_break {
+            self.partial.tail_column = next_tab_stop(self.partial.tail_column);
+        } else {
+            self.partial.head.push_tab();
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.partial.line_break {
+            self.partial.max_inner_line = self.partial.max_inner_line.max(self.partial.tail_column);
+            self.partial.tail_column = 0;
+        } else {
+            self.partial.line_break = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_in_chunks(input: &[u8], chunk_size: usize) -> Stats {
+        let mut counter = StreamCounter::default();
+        for chunk in input.chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        counter.finish()
+    }
+
+    // 断言任意块大小 (包括逐字节) 都与整体统计结果相同，并返回整体结果
+    fn assert_chunking_invariant(input: &[u8]) -> Stats {
+        let whole = count_in_chunks(input, input.len().max(1));
+        for chunk_size in 1..=input.len() {
+            assert_eq!(count_in_chunks(input, chunk_size), whole, "chunk_size = {}", chunk_size);
+        }
+        whole
+    }
+
+    #[test]
+    fn test_chunked_matches_whole() {
+        let content = "hello world\n你好 Rustaceans 😊\u{3000}全角空格\nend".as_bytes();
+        let whole = assert_chunking_invariant(content);
+        assert_eq!(whole.lines, 2);
+        assert_eq!(whole.words, 7);
+        assert_eq!(whole.bytes, content.len());
+        assert_eq!(whole.chars, 36);
+        assert_eq!(whole.max_line_length, 28); // 你好(4) + 空格(1) + Rustaceans(10) + 空格(1) + 😊(2) + U+3000(2) + 全角空格(8)
+        assert_eq!(whole.invalid, 0);
+    }
+
+    #[test]
+    fn test_merge_partials_matches_whole() {
+        // 切分点只落在非延续字节上，与 chunked 模块的切分规则一致
+        let inputs: [&[u8]; 6] = [
+            "hello world\n你好 Rustaceans 😊\u{3000}全角空格\nend".as_bytes(),
+            b"a\xffb \xff\xfe c\x80\x80d\n",
+            b"  leading and trailing  ",
+            b"\n\nno\ttrailing\nnewline",
+            b"a\tbc\tdefghijk\tl\rxy\x0c\t\tz\n12345678\t\t9",
+            b"\x01word\x02 \x7f\x1b[0m",
+        ];
+        for input in inputs {
+            let whole = count_in_chunks(input, input.len().max(1));
+            for split in 0..=input.len() {
+                if input.get(split).is_some_and(|&b| (0x80..0xC0).contains(&b)) {
+                    continue;
+                }
+                let (left, right) = input.split_at(split);
+                let mut left_counter = StreamCounter::default();
+                left_counter.update(left);
+                let mut right_counter = StreamCounter::default();
+                right_counter.update(right);
+                let merged = left_counter.finish_partial().merge(right_counter.finish_partial()).finish();
+                assert_eq!(merged, whole, "input = {:?}, split = {}", input, split);
+            }
+        }
+    }
+
+    #[test]
+    fn test_word_straddles_boundary() {
+        let mut counter = StreamCounter::default();
+        counter.update(b"hel");
+        counter.update(b"lo wor");
+        counter.update(b"ld");
+        let stats = counter.finish();
+        assert_eq!(stats.words, 2);
+        assert_eq!(stats.lines, 0); // 没有换行符
+    }
+
+    #[test]
+    fn test_gnu_word_rules() {
+        // 不可打印字符不开始也不结束单词
+        assert_eq!(assert_chunking_invariant(b"a\x01b").words, 1);
+        assert_eq!(assert_chunking_invariant(b"\x01 \x01").words, 0);
+        // NBSP、U+2007、U+202F、U+2060 是分隔符；U+0085、U+2028 不是
+        for (text, words) in [("a\u{a0}b", 2), ("a\u{2007}b", 2), ("a\u{202f}b", 2), ("a\u{2060}b", 2), ("a\u{85}b", 1), ("a\u{2028}b", 1)] {
+            assert_eq!(assert_chunking_invariant(text.as_bytes()).words, words, "{:?}", text);
+        }
+        // 零宽空格是可打印字符，单独出现时也构成一个单词
+        assert_eq!(assert_chunking_invariant("\u{200b}".as_bytes()).words, 1);
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        assert_eq!(assert_chunking_invariant(b"a\tb\n").max_line_length, 9);
+        assert_eq!(assert_chunking_invariant("a\tb\n你好\n".as_bytes()).max_line_length, 9);
+        assert_eq!(assert_chunking_invariant("你好\n".as_bytes()).max_line_length, 4);
+        // '\r' 重新从第 0 列开始
+        assert_eq!(assert_chunking_invariant(b"ab\rc\n").max_line_length, 2);
+        // 控制字符和无效字节的宽度为 0
+        assert_eq!(assert_chunking_invariant(b"\x01\x01\n\xe9ab\n").max_line_length, 2);
+        // 没有换行符的最后一行也参与计算
+        assert_eq!(assert_chunking_invariant(b"short\nthe longest one").max_line_length, 15);
+    }
+
+    #[test]
+    fn test_invalid_utf8_policy() {
+        // 无效字节不计入字符，也不拆分单词
+        let stats = assert_chunking_invariant(b"a\xffb");
+        assert_eq!((stats.words, stats.chars, stats.bytes, stats.invalid), (1, 2, 3, 1));
+
+        // 被截断的 "你" (E4 BD) 后跟空格：前两个字节构成一个无效序列
+        let stats = assert_chunking_invariant(b"\xe4\xbd a\n");
+        assert_eq!((stats.lines, stats.words, stats.chars, stats.invalid), (1, 1, 3, 1));
+
+        // 每个孤立的延续字节都是一个独立的无效序列
+        let stats = assert_chunking_invariant(b"\x80\x80\x80");
+        assert_eq!((stats.words, stats.chars, stats.invalid), (0, 0, 3));
+
+        // Latin-1 编码的 "café\n"
+        let stats = assert_chunking_invariant(b"caf\xe9\n");
+        assert_eq!((stats.lines, stats.words, stats.chars, stats.invalid), (1, 1, 4, 1));
+    }
+
+    #[test]
+    fn test_custom_terminator() {
+        let count = |input: &[u8], terminator: u8| {
+            let mut counter = StreamCounter::with_terminator(terminator);
+            for chunk in input.chunks(3) {
+                counter.update(chunk);
+            }
+            counter.finish()
+        };
+        // NUL 分隔：NUL 计为行并分隔单词，'\n' 只是空白
+        let stats = count(b"a b\0c\nd\0e", b'\0');
+        assert_eq!((stats.lines, stats.words, stats.max_line_length), (2, 5, 3));
+        // 只有 '\r' 的旧式 Mac 换行
+        let stats = count(b"one\rtwo three\r", b'\r');
+        assert_eq!((stats.lines, stats.words, stats.max_line_length), (2, 3, 9));
+        // 可打印的行结束符不计入宽度
+        let stats = count(b"ab;cde;", b';');
+        assert_eq!((stats.lines, stats.words, stats.chars, stats.max_line_length), (2, 2, 7, 3));
+    }
+
+    #[test]
+    fn test_truncated_at_eof_is_invalid() {
+        let stats = assert_chunking_invariant(&"你".as_bytes()[..2]);
+        assert_eq!((stats.bytes, stats.chars, stats.invalid), (2, 0, 1));
+    }
+}
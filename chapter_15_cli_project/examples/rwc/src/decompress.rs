@@ -0,0 +1,181 @@
+// --decompress：在统计之前透明地解压 gzip、zstd、xz 和 bzip2 输入。
+//
+// `Decompress::Auto` 按文件开头的魔数识别格式，无法识别的输入按原样统计；
+// 指定格式时所有输入都按该格式解码，格式不符时报告解码错误。
+// 连接在一起的多个压缩流 (例如 `cat a.gz b.gz`) 会被依次解码。
+// 压缩数据本身的字节数记在 `Stats::compressed_bytes` 中，其余计数都针对解压后的内容。
+
+use std::cell::Cell;
+use std::io::{self, BufRead, BufReader, Read};
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// 压缩格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Compression {
+    /// 按魔数识别格式；`header` 是输入开头的若干字节
+    pub fn detect(header: &[u8]) -> Option<Compression> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if header.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+
+    /// 文件名中表示该格式的扩展名
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Compression::Gzip => &["gz", "tgz"],
+            Compression::Zstd => &["zst"],
+            Compression::Xz => &["xz", "txz"],
+            Compression::Bzip2 => &["bz2", "tbz2"],
+        }
+    }
+}
+
+/// `Counter::decompress` 的取值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decompress {
+    /// 按魔数识别，无法识别的输入不解压
+    Auto,
+    /// 所有输入都按指定格式解压
+    Format(Compression),
+}
+
+impl FromStr for Decompress {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(Decompress::Auto),
+            "gzip" | "gz" => Ok(Decompress::Format(Compression::Gzip)),
+            "zstd" | "zst" => Ok(Decompress::Format(Compression::Zstd)),
+            "xz" => Ok(Decompress::Format(Compression::Xz)),
+            "bzip2" | "bz2" => Ok(Decompress::Format(Compression::Bzip2)),
+            _ => Err(format!("不支持的压缩格式 '{}' (可选 auto、gzip、zstd、xz、bzip2)", value)),
+        }
+    }
+}
+
+// 统计经过的字节数的读取器，用来得到压缩数据的大小
+pub struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> CountingReader<R> {
+    pub fn new(inner: R) -> (Self, Rc<Cell<u64>>) {
+        let count = Rc::new(Cell::new(0));
+        (CountingReader { inner, count: Rc::clone(&count) }, count)
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+// 按 `mode` 包装输入：返回解码后的读取器和识别出的格式 (None 表示按原样读取)
+pub fn wrap<'a, R: Read + 'a>(input: R, mode: Decompress) -> io::Result<(Box<dyn Read + 'a>, Option<Compression>)> {
+    let mut input = BufReader::new(input);
+    let compression = match mode {
+        Decompress::Format(compression) => Some(compression),
+        Decompress::Auto => detect_buffered(&mut input)?,
+    };
+    let reader: Box<dyn Read + 'a> = match compression {
+        None => Box::new(input),
+        Some(Compression::Gzip) => Box::new(flate2::bufread::MultiGzDecoder::new(input)),
+        Some(Compression::Zstd) => Box::new(zstd::stream::read::Decoder::with_buffer(input)?),
+        Some(Compression::Xz) => Box::new(xz2::bufread::XzDecoder::new_multi_decoder(input)),
+        Some(Compression::Bzip2) => Box::new(bzip2::bufread::MultiBzDecoder::new(input)),
+    };
+    Ok((reader, compression))
+}
+
+// 查看缓冲区开头的字节 (不消耗它们) 来识别格式；输入来自管道时第一次读取可能很短，
+// 但魔数至多 6 个字节，几乎总是在第一次读取中
+fn detect_buffered<R: Read>(input: &mut BufReader<R>) -> io::Result<Option<Compression>> {
+    loop {
+        match input.fill_buf() {
+            Ok(buf) => return Ok(Compression::detect(buf)),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TEXT: &[u8] = "第一行 line one\nsecond line\n".as_bytes();
+
+    fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::encode_all(data, 0).unwrap(),
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Bzip2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    fn decode(input: &[u8], mode: Decompress) -> (Vec<u8>, Option<Compression>) {
+        let (mut reader, compression) = wrap(input, mode).unwrap();
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        (output, compression)
+    }
+
+    #[test]
+    fn test_auto_detects_every_format() {
+        for compression in [Compression::Gzip, Compression::Zstd, Compression::Xz, Compression::Bzip2] {
+            let compressed = compress(compression, TEXT);
+            assert_eq!(decode(&compressed, Decompress::Auto), (TEXT.to_vec(), Some(compression)));
+            assert_eq!(decode(&compressed, Decompress::Format(compression)).0, TEXT);
+        }
+        // 未压缩的输入按原样读取
+        assert_eq!(decode(TEXT, Decompress::Auto), (TEXT.to_vec(), None));
+    }
+
+    #[test]
+    fn test_concatenated_streams() {
+        let mut input = compress(Compression::Gzip, b"one\n");
+        input.extend(compress(Compression::Gzip, b"two\n"));
+        assert_eq!(decode(&input, Decompress::Auto).0, b"one\ntwo\n");
+    }
+
+    #[test]
+    fn test_wrong_format_is_an_error() {
+        let (mut reader, _) = wrap(TEXT, Decompress::Format(Compression::Gzip)).unwrap();
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+    }
+}
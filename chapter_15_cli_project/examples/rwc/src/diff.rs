@@ -0,0 +1,479 @@
+// rwc diff OLD NEW：比较两组文件的计数，输出每个文件的行、词、字节数的变化。
+//
+// OLD 和 NEW 各是一组文件，按路径配对：
+// - 目录：递归统计其中的所有文件 (与 --recursive 相同，支持 --include/--exclude/--gitignore)，
+//   路径相对于该目录；
+// - 文件列表：每行一个路径 (例如 `find old -type f` 的输出)，相对于当前目录，按列表中的写法配对；
+//   --strip N 去掉两侧路径开头的 N 层目录之后再配对 (与 patch -p 相同)，
+//   例如 --strip 1 时 old/src/lib.rs 与 new/src/lib.rs 都是 src/lib.rs；
+// - --git 时是当前仓库中的两个版本 (提交、分支、标签等)：统计 `git ls-tree` 列出的文件内容，
+//   不需要检出。无论在仓库的哪个子目录中运行，都比较整个版本，路径相对于仓库根目录。
+//   符号链接和子模块不统计。
+//
+// 只在一侧出现的文件标记为新增 (A) 或删除 (D)，两侧都有且计数不同的标记为修改 (M)；
+// 计数相同的文件默认不输出 (--all 时标记为 =)。最后总是输出总计的变化。
+// - table: 变化量右对齐 (带正负号)，然后是状态和文件名；
+// - json:  `{"files": [...], "total": {...}}`，每项包含变化量以及 `old`、`new` 两侧的计数；
+// - csv / tsv: 表头为 `kind,file,status,lines,words,bytes,old_lines,...,new_bytes`，缺少的一侧留空。
+
+use crate::output::{escape_csv, escape_tsv, Counts, Field, Format};
+use crate::status::{self, Failures, Stop};
+use crate::{io_error_message, walk, Input};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use rwc::{parallel, Counter, Stats};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::thread;
+
+// 比较的计数器
+const FIELDS: [Field; 3] = [Field::Lines, Field::Words, Field::Bytes];
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    #[clap(value_name = "OLD", help = "旧的一组文件：目录、文件列表 (每行一个路径)，或者 --git 时的 git 版本")]
+    old: PathBuf,
+
+    #[clap(value_name = "NEW", help = "新的一组文件，与 OLD 的形式相同")]
+    new: PathBuf,
+
+    #[clap(long, help = "OLD 和 NEW 是当前 git 仓库中的版本 (提交、分支、标签等)，直接统计版本中的文件内容")]
+    git: bool,
+
+    #[clap(long, help = "同时列出计数没有变化的文件")]
+    all: bool,
+
+    #[clap(
+        long,
+        value_name = "GLOB",
+        value_parser = walk::parse_glob,
+        help = "只比较匹配 GLOB 的文件 (与相对路径或文件名匹配，可多次指定；不影响文件列表)"
+    )]
+    include: Vec<globset::Glob>,
+
+    #[clap(
+        long,
+        value_name = "GLOB",
+        value_parser = walk::parse_glob,
+        help = "跳过匹配 GLOB 的文件和目录 (与相对路径或文件名匹配，可多次指定；不影响文件列表)"
+    )]
+    exclude: Vec<globset::Glob>,
+
+    #[clap(long, conflicts_with = "git", help = "遍历目录时遵循 .gitignore 文件并跳过 .git 目录")]
+    gitignore: bool,
+
+    #[clap(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        conflicts_with = "git",
+        help = "配对之前去掉文件列表中路径开头的 N 层目录 (与 patch -p 相同，不影响目录)"
+    )]
+    strip: usize,
+
+    #[clap(short = 'j', long, value_name = "N", default_value_t = 1, help = "使用 N 个线程并行统计 (0 表示使用全部 CPU)")]
+    jobs: usize,
+
+    #[clap(long, value_enum, default_value_t = Format::Table, help = "输出格式：table、json、csv 或 tsv")]
+    pub format: Format, // 可以由配置文件设置默认值
+
+    #[clap(long, help = "遇到第一个无法统计的文件时立即停止，不输出比较结果")]
+    strict: bool,
+}
+
+// 一侧的计数，按路径排序
+type Side = BTreeMap<String, Stats>;
+
+pub fn run(args: &DiffArgs) -> Result<ExitCode> {
+    let filter = walk::Filter::new(&args.include, &args.exclude, args.gitignore)?;
+    let counter = Counter::new().lines(true).words(true).bytes(true);
+    let mut failures = Failures::new(args.strict);
+    let mut input_count = 0;
+
+    let (old, new) = if args.git {
+        (count_revision(&args.old, &filter, &counter)?, count_revision(&args.new, &filter, &counter)?)
+    } else {
+        let mut inputs = Vec::new();
+        for root in [&args.old, &args.new] {
+            match side_inputs(root, &filter, args.strip) {
+                Ok(side) => inputs.push(side),
+                Err(e) => {
+                    eprintln!("rwc: cannot open '{}' for reading: {}", root.display(), io_error_message(&e));
+                    return Ok(ExitCode::from(status::FAILURE));
+                }
+            }
+        }
+        input_count = inputs.iter().map(Vec::len).sum();
+        let jobs = parallel::effective_jobs(args.jobs);
+        let mut count = |inputs: &[(String, Input)]| count_inputs(inputs, &counter, jobs, &mut failures);
+        let counted = count(&inputs[0]).and_then(|old| Ok((old, count(&inputs[1])?)));
+        match counted {
+            Ok(sides) => sides,
+            Err(Stop::Strict) => return Ok(failures.exit_code(input_count)),
+            Err(Stop::Io(e)) => return Err(e.into()),
+        }
+    };
+
+    let changes = compare(old, new, args.all);
+    let mut out = io::stdout().lock();
+    match args.format {
+        Format::Table => write_table(&mut out, &changes)?,
+        Format::Json => write_json(&mut out, &changes)?,
+        Format::Csv | Format::Tsv => write_delimited(&mut out, &changes, args.format)?,
+    }
+    out.flush()?;
+    Ok(failures.exit_code(input_count))
+}
+
+// 列出一个目录或文件列表中的文件，以及它们用于配对的路径
+fn side_inputs(root: &Path, filter: &walk::Filter, strip: usize) -> io::Result<Vec<(String, Input)>> {
+    if root.is_dir() {
+        let inputs = filter.expand(vec![Input::Path(root.to_path_buf())]);
+        return Ok(inputs
+            .into_iter()
+            .map(|input| {
+                let key = match &input {
+                    Input::Path(path) => path.strip_prefix(root).unwrap_or(path).to_string_lossy().into_owned(),
+                    _ => String::new(), // 无法遍历的目录：只报告错误
+                };
+                (key, input)
+            })
+            .collect());
+    }
+    let list = fs::read_to_string(root)?;
+    Ok(list
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| (strip_components(Path::new(line), strip), Input::Path(PathBuf::from(line))))
+        .collect())
+}
+
+// 去掉 `path` 开头的 `count` 层目录，文件名总是保留
+fn strip_components(path: &Path, count: usize) -> String {
+    let components: Vec<_> = path.components().collect();
+    let count = count.min(components.len().saturating_sub(1));
+    components[count..].iter().collect::<PathBuf>().to_string_lossy().into_owned()
+}
+
+fn count_inputs(inputs: &[(String, Input)], counter: &Counter, jobs: usize, failures: &mut Failures) -> Result<Side, Stop> {
+    let mut side = Side::new();
+    parallel::for_each_ordered(
+        inputs,
+        jobs,
+        |(_, input)| match input {
+            Input::Path(path) => counter.count_file(path),
+            Input::Invalid(message) => Err(io::Error::new(io::ErrorKind::InvalidInput, message.clone())),
+            Input::Stdin => unreachable!("目录和文件列表中没有 stdin"),
+        },
+        |index, outcome| -> Result<(), Stop> {
+            let (key, input) = &inputs[index];
+            match outcome {
+                Ok(stats) => {
+                    side.insert(key.clone(), stats);
+                }
+                Err(e) => failures.record(input, &e)?,
+            }
+            Ok(())
+        },
+    )?;
+    Ok(side)
+}
+
+// 统计 git 版本 `revision` 中的文件：`git ls-tree` 列出文件，`git cat-file --batch` 依次输出内容
+fn count_revision(revision: &Path, filter: &walk::Filter, counter: &Counter) -> Result<Side> {
+    let listing = Command::new("git")
+        .args(["ls-tree", "-r", "-z", "--full-tree"]) // 不加 --full-tree 时只列出当前目录下的部分
+        .arg(revision)
+        .stderr(Stdio::inherit())
+        .output()
+        .context("无法运行 git")?;
+    if !listing.status.success() {
+        bail!("无法列出 git 版本 '{}' 中的文件", revision.display());
+    }
+    // 每项为 "<mode> <type> <object>\t<path>"
+    let mut files = Vec::new();
+    for entry in listing.stdout.split(|&b| b == 0).filter(|entry| !entry.is_empty()) {
+        let entry = String::from_utf8_lossy(entry);
+        let Some((meta, path)) = entry.split_once('\t') else { continue };
+        let mut meta = meta.split(' ');
+        let (Some(mode), Some("blob"), Some(object)) = (meta.next(), meta.next(), meta.next()) else {
+            continue; // 子模块
+        };
+        if mode != "120000" && filter.accepts(Path::new(path)) {
+            files.push((path.to_string(), object.to_string()));
+        }
+    }
+
+    let mut child = Command::new("git")
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("无法运行 git")?;
+    let mut stdin = io::BufWriter::new(child.stdin.take().expect("stdin 已设置为管道"));
+    let objects: Vec<String> = files.iter().map(|(_, object)| object.clone()).collect();
+    // 在另一个线程中写入对象名，避免两个方向的管道缓冲区都满时互相等待
+    let writer = thread::spawn(move || -> io::Result<()> {
+        for object in objects {
+            writeln!(stdin, "{}", object)?;
+        }
+        stdin.flush() // 随后关闭管道，git 读到结尾后退出
+    });
+
+    let mut contents = BufReader::new(child.stdout.take().expect("stdout 已设置为管道"));
+    let counted = read_objects(&mut contents, files, counter);
+    // 出错时剩下的输出不再需要：结束 git，写入线程随之因为管道关闭而结束；无论成败都等待两者
+    if counted.is_err() {
+        let _ = child.kill();
+    }
+    drop(contents);
+    let written = writer.join().expect("写入线程不会 panic");
+    let status = child.wait()?;
+    let side = counted?;
+    written?;
+    if !status.success() {
+        bail!("git cat-file 异常退出 ({})", status);
+    }
+    Ok(side)
+}
+
+// 依次读取 `git cat-file --batch` 输出的 `files` 中每个对象的内容并统计
+fn read_objects(contents: &mut impl BufRead, files: Vec<(String, String)>, counter: &Counter) -> Result<Side> {
+    let mut side = Side::new();
+    for (path, _) in files {
+        // 每个对象为 "<object> <type> <size>\n<内容>\n"
+        let mut header = String::new();
+        contents.read_line(&mut header)?;
+        let size: u64 = header
+            .split_whitespace()
+            .nth(2)
+            .and_then(|size| size.parse().ok())
+            .with_context(|| format!("git cat-file 的输出无法解析: {}", header.trim_end()))?;
+        let stats = counter.count_reader(contents.take(size))?;
+        contents.read_exact(&mut [0; 1])?;
+        side.insert(path, stats);
+    }
+    Ok(side)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+impl Status {
+    fn letter(self) -> char {
+        match self {
+            Status::Added => 'A',
+            Status::Removed => 'D',
+            Status::Modified => 'M',
+            Status::Unchanged => '=',
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Status::Added => "added",
+            Status::Removed => "removed",
+            Status::Modified => "modified",
+            Status::Unchanged => "unchanged",
+        }
+    }
+}
+
+// 一个文件的变化；新增的文件没有 old，删除的文件没有 new
+struct Change {
+    file: String,
+    status: Status,
+    old: Option<Stats>,
+    new: Option<Stats>,
+}
+
+impl Change {
+    fn delta(&self, field: Field) -> i64 {
+        let value = |stats: Option<Stats>| stats.map_or(0, |stats| field.value(&stats) as i64);
+        value(self.new) - value(self.old)
+    }
+}
+
+// 按路径配对两侧的计数，最后一项是总计 (file 为空)
+fn compare(mut old: Side, new: Side, all: bool) -> Vec<Change> {
+    let old_total: Stats = old.values().sum();
+    let new_total: Stats = new.values().sum();
+    let mut changes: Vec<Change> = Vec::new();
+    for (file, new_stats) in new {
+        let old_stats = old.remove(&file);
+        let status = match old_stats {
+            None => Status::Added,
+            Some(old_stats) if old_stats == new_stats => Status::Unchanged,
+            Some(_) => Status::Modified,
+        };
+        changes.push(Change { file, status, old: old_stats, new: Some(new_stats) });
+    }
+    changes.extend(old.into_iter().map(|(file, stats)| Change { file, status: Status::Removed, old: Some(stats), new: None }));
+    changes.retain(|change| all || change.status != Status::Unchanged);
+    changes.sort_by(|a, b| a.file.cmp(&b.file));
+
+    let status = if old_total == new_total { Status::Unchanged } else { Status::Modified };
+    changes.push(Change { file: String::new(), status, old: Some(old_total), new: Some(new_total) });
+    changes
+}
+
+// 带正负号的变化量，没有变化时为 0
+fn signed(delta: i64) -> String {
+    if delta == 0 { "0".to_string() } else { format!("{:+}", delta) }
+}
+
+fn write_table<W: Write>(out: &mut W, changes: &[Change]) -> io::Result<()> {
+    let rows: Vec<Vec<String>> = changes.iter().map(|change| FIELDS.iter().map(|&field| signed(change.delta(field))).collect()).collect();
+    // 与计数表一样，所有列使用相同的宽度
+    let width = rows.iter().flatten().map(String::len).max().unwrap_or(1);
+    for (i, (change, row)) in changes.iter().zip(&rows).enumerate() {
+        let cells: Vec<String> = row.iter().map(|cell| format!("{:>width$}", cell)).collect();
+        if i + 1 == changes.len() {
+            writeln!(out, "{}   total", cells.join(" "))?;
+        } else {
+            writeln!(out, "{} {} {}", cells.join(" "), change.status.letter(), change.file)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Deltas {
+    lines: i64,
+    words: i64,
+    bytes: i64,
+}
+
+#[derive(Serialize)]
+struct ChangeRecord<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    status: &'static str,
+    #[serde(flatten)]
+    deltas: Deltas,
+    old: Option<Counts>,
+    new: Option<Counts>,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    files: Vec<ChangeRecord<'a>>,
+    total: ChangeRecord<'a>,
+}
+
+// 总计没有 file 字段
+fn record(change: &Change, is_total: bool) -> ChangeRecord<'_> {
+    ChangeRecord {
+        file: (!is_total).then_some(change.file.as_str()),
+        status: change.status.name(),
+        deltas: Deltas {
+            lines: change.delta(Field::Lines),
+            words: change.delta(Field::Words),
+            bytes: change.delta(Field::Bytes),
+        },
+        old: change.old.map(|stats| Counts::new(&FIELDS, &stats)),
+        new: change.new.map(|stats| Counts::new(&FIELDS, &stats)),
+    }
+}
+
+fn write_json<W: Write>(out: &mut W, changes: &[Change]) -> io::Result<()> {
+    let (total, files) = changes.split_last().expect("总是有总计");
+    let report = JsonReport {
+        files: files.iter().map(|change| record(change, false)).collect(),
+        total: record(total, true),
+    };
+    serde_json::to_writer(&mut *out, &report)?;
+    writeln!(out)
+}
+
+fn write_delimited<W: Write>(out: &mut W, changes: &[Change], format: Format) -> io::Result<()> {
+    let (separator, escape): (&str, fn(&str) -> String) = match format {
+        Format::Csv => (",", escape_csv),
+        _ => ("\t", escape_tsv),
+    };
+    let mut header = vec!["kind".to_string(), "file".to_string(), "status".to_string()];
+    header.extend(FIELDS.iter().map(|field| field.name().to_string()));
+    for side in ["old", "new"] {
+        header.extend(FIELDS.iter().map(|field| format!("{}_{}", side, field.name())));
+    }
+    writeln!(out, "{}", header.join(separator))?;
+
+    for (i, change) in changes.iter().enumerate() {
+        let kind = if i + 1 == changes.len() { "total" } else { "file" };
+        let mut record = vec![kind.to_string(), escape(&change.file), change.status.name().to_string()];
+        record.extend(FIELDS.iter().map(|&field| change.delta(field).to_string()));
+        for stats in [change.old, change.new] {
+            // 缺少的一侧留空
+            record.extend(FIELDS.iter().map(|field| stats.map_or(String::new(), |stats| field.value(&stats).to_string())));
+        }
+        writeln!(out, "{}", record.join(separator))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn side(entries: &[(&str, usize, usize, usize)]) -> Side {
+        entries
+            .iter()
+            .map(|&(file, lines, words, bytes)| (file.to_string(), Stats { lines, words, bytes, ..Stats::default() }))
+            .collect()
+    }
+
+    fn render(format: Format, all: bool) -> String {
+        let old = side(&[("same", 1, 1, 2), ("gone", 2, 4, 20), ("grow", 10, 20, 100)]);
+        let new = side(&[("same", 1, 1, 2), ("grow", 12, 19, 130), ("new", 1, 2, 9)]);
+        let changes = compare(old, new, all);
+        let mut out = Vec::new();
+        match format {
+            Format::Table => write_table(&mut out, &changes).unwrap(),
+            Format::Json => write_json(&mut out, &changes).unwrap(),
+            Format::Csv | Format::Tsv => write_delimited(&mut out, &changes, format).unwrap(),
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            render(Format::Table, false),
+            " -2  -4 -20 D gone\n +2  -1 +30 M grow\n +1  +2  +9 A new\n +1  -3 +19   total\n"
+        );
+        assert!(render(Format::Table, true).contains("  0   0   0 = same\n"));
+    }
+
+    #[test]
+    fn test_machine_readable() {
+        let json = render(Format::Json, false);
+        assert!(json.starts_with(
+            "{\"files\":[{\"file\":\"gone\",\"status\":\"removed\",\"lines\":-2,\"words\":-4,\"bytes\":-20,\
+             \"old\":{\"lines\":2,\"words\":4,\"bytes\":20},\"new\":null},"
+        ));
+        assert!(json.ends_with(
+            "\"total\":{\"status\":\"modified\",\"lines\":1,\"words\":-3,\"bytes\":19,\
+             \"old\":{\"lines\":13,\"words\":25,\"bytes\":122},\"new\":{\"lines\":14,\"words\":22,\"bytes\":141}}}\n"
+        ));
+        let csv = render(Format::Csv, false);
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            [
+                "kind,file,status,lines,words,bytes,old_lines,old_words,old_bytes,new_lines,new_words,new_bytes",
+                "file,gone,removed,-2,-4,-20,2,4,20,,,",
+                "file,grow,modified,2,-1,30,10,20,100,12,19,130",
+                "file,new,added,1,2,9,,,,1,2,9",
+                "total,,modified,1,-3,19,13,25,122,14,22,141",
+            ]
+        );
+    }
+}
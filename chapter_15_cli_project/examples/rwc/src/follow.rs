@@ -0,0 +1,177 @@
+// --follow：持续跟踪文件的增长并定期重新输出计数表 (类似 `tail -F`)。
+//
+// 每个文件保持打开，每次轮询只读取新追加的字节并交给 `rwc::Tally` 增量统计，不会从头重新读取。
+// - 截断：文件变得比已读取的部分短时，从头重新统计；
+// - 轮转：路径指向了另一个文件 (例如 logrotate 把旧文件改名后新建了同名文件) 时，
+//   读完旧文件剩余的内容后改为跟踪新文件，计数从新文件开始 (计数始终描述路径当前指向的文件)；
+// - 文件不存在或被删除时保留最后的计数，等它重新出现。
+//
+// 输出到终端时每次清屏重画；输出到管道或文件时，每当计数变化就追加一份完整的输出。
+
+use crate::output::{self, Reporter};
+use crate::{counter_from_cli, io_error_message, table_width, Cli, Input};
+use anyhow::Result;
+use clap::error::ErrorKind;
+use clap::CommandFactory;
+use rwc::{Counter, Language, Stats, Tally};
+use std::fs::{self, File, Metadata};
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+// --interval 的参数解析器
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value.parse().map_err(|_| format!("无效的秒数: {}", value))?;
+    if !(seconds > 0.0 && seconds.is_finite()) {
+        return Err("间隔必须是正数".to_string());
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+pub fn run(cli: &Cli, inputs: Vec<Input>) -> Result<()> {
+    let counter = counter_from_cli(cli);
+    let mut files = Vec::new();
+    for input in inputs {
+        match input {
+            Input::Path(path) => files.push(Followed::new(path, &counter)),
+            // 用法错误，与 clap 报告的其他用法错误一样以 2 退出
+            Input::Stdin => Cli::command().error(ErrorKind::ArgumentConflict, "--follow 不能跟踪 stdin").exit(),
+            Input::Invalid(message) => eprintln!("rwc: {}", message),
+        }
+    }
+
+    let tty = io::stdout().is_terminal();
+    let mut last_drawn: Option<Vec<Stats>> = None;
+    loop {
+        for file in &mut files {
+            file.poll(&counter);
+        }
+        let snapshot: Vec<Stats> = files.iter().map(|file| file.tally.snapshot()).collect();
+        if last_drawn.as_ref() != Some(&snapshot) {
+            draw(cli, &files, &snapshot, tty)?;
+            last_drawn = Some(snapshot);
+        }
+        thread::sleep(cli.interval);
+    }
+}
+
+fn draw(cli: &Cli, files: &[Followed], snapshot: &[Stats], tty: bool) -> io::Result<()> {
+    let inputs: Vec<Input> = files.iter().map(|file| Input::Path(file.path.clone())).collect();
+    let fields = output::requested_fields(cli);
+    let width = table_width(cli, &inputs, fields.len(), false);
+    let mut out = io::stdout().lock();
+    if tty {
+        write!(out, "\x1b[H\x1b[2J")?; // 光标移到左上角并清屏
+    }
+    let mut reporter = Reporter::new(out, cli.format, fields, width, cli.total);
+    for (input, stats) in inputs.iter().zip(snapshot) {
+        reporter.file(Some(&input.name()), stats)?;
+    }
+    reporter.finish(&snapshot.iter().sum(), inputs.len())
+}
+
+// 一个被跟踪的文件
+struct Followed {
+    path: PathBuf,
+    language: Option<Language>,
+    file: Option<File>,
+    id: Option<FileId>,
+    position: u64, // 已经统计到的位置
+    tally: Tally,
+    last_error: Option<String>, // 同一个错误只报告一次
+}
+
+impl Followed {
+    fn new(path: PathBuf, counter: &Counter) -> Self {
+        let language = Language::from_path(&path);
+        let tally = counter.tally(language);
+        let mut followed = Followed { path, language, file: None, id: None, position: 0, tally, last_error: None };
+        followed.open(counter);
+        followed
+    }
+
+    // 打开路径当前指向的文件，从头开始统计
+    fn open(&mut self, counter: &Counter) {
+        match File::open(&self.path).and_then(|file| file.metadata().map(|metadata| (file, metadata))) {
+            Ok((file, metadata)) => {
+                self.file = Some(file);
+                self.id = file_id(&metadata);
+                self.position = 0;
+                self.tally = counter.tally(self.language);
+            }
+            Err(e) => self.report(e),
+        }
+    }
+
+    fn poll(&mut self, counter: &Counter) {
+        if self.file.is_none() {
+            self.open(counter);
+        }
+        let Some(file) = &mut self.file else { return };
+
+        // 截断：从头重新统计
+        if file.metadata().is_ok_and(|metadata| metadata.len() < self.position) {
+            if let Err(e) = file.seek(SeekFrom::Start(0)) {
+                return self.report(e);
+            }
+            self.position = 0;
+            self.tally = counter.tally(self.language);
+        }
+        self.read_appended();
+
+        // 轮转：旧文件剩余的内容已经读完，改为跟踪新文件
+        if let Ok(metadata) = fs::metadata(&self.path) {
+            if file_id(&metadata) != self.id {
+                self.open(counter);
+                self.read_appended();
+            }
+        }
+    }
+
+    fn read_appended(&mut self) {
+        let Some(file) = &mut self.file else { return };
+        let mut byte_buffer = [0; 8192];
+        loop {
+            match file.read(&mut byte_buffer) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.tally.update(&byte_buffer[..n]);
+                    self.position += n as u64;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    // 例如目录：不再读取，之后每次轮询重新打开，路径变成普通文件时就能恢复
+                    self.file = None;
+                    return self.report(e);
+                }
+            }
+        }
+        self.last_error = None;
+    }
+
+    fn report(&mut self, e: io::Error) {
+        let message = io_error_message(&e);
+        if self.last_error.as_ref() != Some(&message) {
+            eprintln!("rwc: {}: {}", self.path.display(), message);
+            self.last_error = Some(message);
+        }
+    }
+}
+
+// 用于识别轮转的文件标识：Unix 上是 (设备号, inode)，其他平台不识别轮转
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = ();
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<FileId> {
+    None
+}
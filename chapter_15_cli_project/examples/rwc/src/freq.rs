@@ -0,0 +1,300 @@
+// --top：频率分析，统计最常见的记号 (单词、字符或相邻单词对) 和行长分布。
+//
+// 记号的定义：
+// - 单词：以空白字符分隔的非空序列，区分大小写 (与 `str::split_whitespace` 相同)；
+// - 字符：每个非空白字符；
+// - 单词对 (bigram)：相邻的两个单词，用一个空格连接，可以跨行。
+// 无效的 UTF-8 序列不属于任何记号，并且总是断开两侧的单词。
+//
+// 行长按字符数计算 (不含换行符，无效序列不计入)，末尾没有换行符的最后一行也算一行。
+// 行长分布按 2 的幂分组：0、1、2-3、4-7、8-15……，组数与最长行的位数成正比。
+
+use std::collections::HashMap;
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+/// 要统计频率的记号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenKind {
+    /// 以空白分隔的单词
+    #[default]
+    Words,
+    /// 非空白字符
+    Chars,
+    /// 相邻的两个单词
+    Bigrams,
+}
+
+impl TokenKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Words => "words",
+            TokenKind::Chars => "chars",
+            TokenKind::Bigrams => "bigrams",
+        }
+    }
+}
+
+impl FromStr for TokenKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "words" => Ok(TokenKind::Words),
+            "chars" => Ok(TokenKind::Chars),
+            "bigrams" => Ok(TokenKind::Bigrams),
+            _ => Err(format!("不支持的记号 '{}' (可选 words、chars、bigrams)", value)),
+        }
+    }
+}
+
+/// 行长分布中的一组：长度在 `min..=max` 之间的行数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    pub min: usize,
+    pub max: usize,
+    pub lines: usize,
+}
+
+/// 一个或多个输入的频率统计结果，可以用 `+=` 合并
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Frequencies {
+    tokens: HashMap<String, usize>,
+    line_lengths: Vec<usize>, // 第 i 组的行数，分组方式见 bucket_of
+}
+
+impl Frequencies {
+    /// 出现次数最多的 `n` 个记号，按次数从多到少排列，次数相同时按记号排序
+    pub fn top(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut tokens: Vec<(&str, usize)> = self.tokens.iter().map(|(token, &count)| (token.as_str(), count)).collect();
+        tokens.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        tokens.truncate(n);
+        tokens
+    }
+
+    /// 不同记号的个数
+    pub fn distinct(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// 行长分布：从最短的非空组到最长的非空组，中间的空组也包含在内
+    pub fn histogram(&self) -> Vec<Bucket> {
+        let Some(first) = self.line_lengths.iter().position(|&lines| lines > 0) else {
+            return Vec::new();
+        };
+        (first..self.line_lengths.len())
+            .map(|i| {
+                let (min, max) = bucket_range(i);
+                Bucket { min, max, lines: self.line_lengths[i] }
+            })
+            .collect()
+    }
+
+    fn add_token(&mut self, token: String) {
+        let count = self.tokens.entry(token).or_insert(0);
+        *count += 1;
+    }
+
+    fn add_line(&mut self, length: usize) {
+        let i = bucket_of(length);
+        if self.line_lengths.len() <= i {
+            self.line_lengths.resize(i + 1, 0);
+        }
+        self.line_lengths[i] += 1;
+    }
+}
+
+impl AddAssign for Frequencies {
+    fn add_assign(&mut self, other: Self) {
+        for (token, count) in other.tokens {
+            *self.tokens.entry(token).or_insert(0) += count;
+        }
+        if self.line_lengths.len() < other.line_lengths.len() {
+            self.line_lengths.resize(other.line_lengths.len(), 0);
+        }
+        for (lines, other_lines) in self.line_lengths.iter_mut().zip(other.line_lengths) {
+            *lines += other_lines;
+        }
+    }
+}
+
+// 长度为 0 的行在第 0 组，长度在 [2^(i-1), 2^i) 之间的行在第 i 组
+fn bucket_of(length: usize) -> usize {
+    (usize::BITS - length.leading_zeros()) as usize
+}
+
+fn bucket_range(i: usize) -> (usize, usize) {
+    if i == 0 { (0, 0) } else { (1 << (i - 1), (1 << (i - 1)) * 2 - 1) }
+}
+
+// 流式的频率统计，与 StreamCounter 一样接受任意切分的字节块
+#[derive(Debug, Default)]
+pub struct FrequencyCounter {
+    by: TokenKind,
+    pending: Vec<u8>,         // 被块边界截断的 UTF-8 序列
+    word: String,             // 当前单词
+    previous: Option<String>, // 上一个单词 (单词对)
+    line_length: usize,
+    carriage_return: bool, // 上一个字符是 '\r'：紧接着 '\n' 时属于行结束符 (CRLF)，不计入行长
+    frequencies: Frequencies,
+}
+
+impl FrequencyCounter {
+    pub fn new(by: TokenKind) -> Self {
+        FrequencyCounter { by, ..FrequencyCounter::default() }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        if self.pending.is_empty() {
+            self.process(bytes);
+        } else {
+            // 只有块边界截断了多字节字符时才需要拼接
+            let mut joined = std::mem::take(&mut self.pending);
+            joined.extend_from_slice(bytes);
+            self.process(&joined);
+        }
+    }
+
+    pub fn finish(mut self) -> Frequencies {
+        if !self.pending.is_empty() {
+            self.end_word(); // 输入末尾不完整的序列是无效序列
+        }
+        self.end_word();
+        if self.line_length > 0 || self.carriage_return {
+            self.frequencies.add_line(self.line_length);
+        }
+        self.frequencies
+    }
+
+    fn process(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.utf8_chunks().peekable();
+        while let Some(chunk) = chunks.next() {
+            for c in chunk.valid().chars() {
+                self.push_char(c);
+            }
+            let invalid = chunk.invalid();
+            if invalid.is_empty() {
+                continue;
+            }
+            // 最后一段无效字节如果是某个字符的前缀 (error_len 为 None)，等下一块数据到来
+            let truncated = chunks.peek().is_none()
+                && std::str::from_utf8(invalid).is_err_and(|e| e.error_len().is_none());
+            if truncated {
+                self.pending.extend_from_slice(invalid);
+            } else {
+                self.end_word();
+            }
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        // 与 -L 一样，CRLF 行末的 '\r' 不计入行长
+        if c == '\n' {
+            self.frequencies.add_line(self.line_length);
+            self.line_length = 0;
+            self.carriage_return = false;
+        } else {
+            if self.carriage_return {
+                self.line_length += 1; // 前一个 '\r' 不在行末
+            }
+            self.carriage_return = c == '\r';
+            if !self.carriage_return {
+                self.line_length += 1;
+            }
+        }
+        if c.is_whitespace() {
+            self.end_word();
+        } else if self.by == TokenKind::Chars {
+            self.frequencies.add_token(c.to_string());
+        } else {
+            self.word.push(c);
+        }
+    }
+
+    fn end_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        match self.by {
+            TokenKind::Words => self.frequencies.add_token(word),
+            TokenKind::Bigrams => {
+                if let Some(previous) = self.previous.replace(word.clone()) {
+                    self.frequencies.add_token(format!("{} {}", previous, word));
+                }
+            }
+            TokenKind::Chars => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(input: &[u8], by: TokenKind, chunk_size: usize) -> Frequencies {
+        let mut counter = FrequencyCounter::new(by);
+        for chunk in input.chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        counter.finish()
+    }
+
+    #[test]
+    fn test_top_words() {
+        // 与 chapter_06 中的 word_counts 示例相同
+        let frequencies = count(b"hello world hello", TokenKind::Words, 1024);
+        assert_eq!(frequencies.top(10), vec![("hello", 2), ("world", 1)]);
+        // 次数相同时按记号排序
+        let frequencies = count(b"b a c a b\n", TokenKind::Words, 1024);
+        assert_eq!(frequencies.top(2), vec![("a", 2), ("b", 2)]);
+        assert_eq!(frequencies.distinct(), 3);
+    }
+
+    #[test]
+    fn test_chars_and_bigrams() {
+        let input = "你好 你\nto be or not to be\n".as_bytes();
+        assert_eq!(count(input, TokenKind::Chars, 1024).top(3), vec![("o", 4), ("t", 3), ("b", 2)]);
+        let bigrams = count(input, TokenKind::Bigrams, 1024);
+        assert_eq!(bigrams.top(2), vec![("to be", 2), ("be or", 1)]);
+        assert!(bigrams.top(10).contains(&("你 to", 1))); // 单词对可以跨行
+    }
+
+    #[test]
+    fn test_chunk_boundaries_and_invalid_bytes() {
+        let input = [&b"ab\xffcd "[..], "第一行 line\n\u{e9}t\u{e9}\n".as_bytes()].concat();
+        let input = input.as_slice();
+        let expected = count(input, TokenKind::Words, 1024);
+        assert_eq!(count(input, TokenKind::Words, 1), expected); // 逐字节输入结果相同
+        assert_eq!(expected.top(10), vec![("ab", 1), ("cd", 1), ("line", 1), ("été", 1), ("第一行", 1)]);
+        // 输入末尾被截断的序列是无效序列
+        assert_eq!(count(b"ab\xe4\xbd", TokenKind::Words, 1).top(10), vec![("ab", 1)]);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let frequencies = count(b"\nabc\nabcdefgh\nxy", TokenKind::Words, 1024);
+        let buckets: Vec<(usize, usize, usize)> =
+            frequencies.histogram().iter().map(|b| (b.min, b.max, b.lines)).collect();
+        assert_eq!(buckets, vec![(0, 0, 1), (1, 1, 0), (2, 3, 2), (4, 7, 0), (8, 15, 1)]);
+
+        let mut merged = frequencies.clone();
+        merged += count(b"x\n", TokenKind::Words, 1024);
+        assert_eq!(merged.histogram()[1].lines, 1);
+        assert_eq!(merged.top(1), vec![("abc", 1)]);
+        assert_eq!(Frequencies::default().histogram(), Vec::new());
+    }
+
+    #[test]
+    fn test_histogram_crlf() {
+        // CRLF 文件的行长与 LF 文件相同；行中间的 '\r' 仍然计入
+        let lf = count(b"abc\nabcdefgh\n\nxy", TokenKind::Words, 1024);
+        for chunk_size in [1, 1024] {
+            let crlf = count(b"abc\r\nabcdefgh\r\n\r\nxy\r", TokenKind::Words, chunk_size);
+            assert_eq!(crlf.histogram(), lf.histogram());
+        }
+        let buckets: Vec<(usize, usize, usize)> =
+            count(b"a\rb\r\r\n", TokenKind::Words, 1).histogram().iter().map(|b| (b.min, b.max, b.lines)).collect();
+        assert_eq!(buckets, vec![(4, 7, 1)]);
+    }
+}
@@ -0,0 +1,62 @@
+// rwc completions / rwc man：由 `Cli` 的定义生成 shell 补全脚本和 man 手册，输出到 stdout。
+//
+// 两者都直接来自 clap 的命令定义，选项增减后不需要另外维护。打包时的用法：
+//
+//     rwc completions bash > /usr/share/bash-completion/completions/rwc
+//     rwc completions zsh > /usr/share/zsh/site-functions/_rwc
+//     rwc completions fish > /usr/share/fish/vendor_completions.d/rwc.fish
+//     rwc man > /usr/share/man/man1/rwc.1
+//
+// man 手册中包含子命令的列表；每个子命令的选项见 `rwc <子命令> --help`。
+
+use crate::Cli;
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use clap_mangen::Man;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+pub fn completions(shell: Shell) -> Result<ExitCode> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    // generate 在写出失败时会 panic，先生成到内存中，写出的错误 (例如 broken pipe) 照常处理
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    let mut out = io::stdout().lock();
+    out.write_all(&script)?;
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn man() -> Result<ExitCode> {
+    let mut out = io::stdout().lock();
+    Man::new(Cli::command()).render(&mut out)?;
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_option_is_generated() {
+        let command = Cli::command();
+        let mut bash = Vec::new();
+        clap_complete::generate(Shell::Bash, &mut command.clone(), "rwc", &mut bash);
+        let bash = String::from_utf8(bash).unwrap();
+        let mut page = Vec::new();
+        Man::new(command.clone()).render(&mut page).unwrap();
+        let page = String::from_utf8(page).unwrap();
+
+        for long in command.get_arguments().filter_map(|arg| arg.get_long()) {
+            assert!(bash.contains(&format!("--{}", long)), "补全脚本中缺少 --{}", long);
+            // roff 中的连字符写作 \-
+            assert!(page.contains(&format!("\\-\\-{}", long.replace('-', "\\-"))), "man 手册中缺少 --{}", long);
+        }
+        for subcommand in command.get_subcommands() {
+            assert!(page.contains(subcommand.get_name()), "man 手册中缺少子命令 {}", subcommand.get_name());
+        }
+    }
+}
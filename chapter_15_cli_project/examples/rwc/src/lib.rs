@@ -0,0 +1,662 @@
+//! rwc 的统计引擎：与 wc 兼容的行、词、字符、字节和最长行统计，
+//! 以及无效 UTF-8 序列、UAX #29 单词和字素、按语言的代码行分类等扩展统计。
+//! 压缩的输入 (gzip、zstd、xz、bzip2) 可以在统计前透明地解压。
+//!
+//! 输入以固定大小的块流式读取，内存占用与输入大小无关；本地的普通文件默认映射到内存中统计。
+//! 输入不必是合法的 UTF-8。
+//!
+//! ```no_run
+//! use rwc::Counter;
+//!
+//! let counter = Counter::new().lines(true).words(true);
+//! let stats = counter.count_reader(std::io::stdin())?;
+//! println!("{} {}", stats.lines, stats.words);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+use std::path::Path;
+
+mod chunked;
+pub mod code;
+mod counter;
+mod decompress;
+pub mod freq;
+mod mapped;
+pub mod parallel;
+mod record;
+mod segment;
+
+pub use code::Language;
+pub use decompress::{Compression, Decompress};
+pub use freq::{Frequencies, TokenKind};
+use code::CodeCounter;
+use counter::StreamCounter;
+use freq::FrequencyCounter;
+use record::RecordCounter;
+use regex::bytes::Regex;
+use segment::SegmentCounter;
+
+/// 一个或多个输入的统计结果。没有请求的统计项为 0。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)] // Default 用于方便初始化，Copy 用于多文件总计
+pub struct Stats {
+    /// 换行符 (或 `line_terminator` 指定的行结束符) 的个数，与 wc 相同，末尾没有行结束符的
+    /// 最后一行不计入；设置了 `record_separator` 时是记录数
+    pub lines: usize,
+    /// 单词数：默认按空白分词 (规则见 counter 模块)，`unicode_words` 时按 UAX #29 单词边界
+    pub words: usize,
+    pub bytes: usize,
+    /// 字符数，无效的 UTF-8 序列不计入
+    pub chars: usize,
+    /// 字素 (用户感知的字符) 数，规则见 segment 模块
+    pub graphemes: usize,
+    /// 最长行的显示宽度，计算规则见 counter 模块
+    pub max_line_length: usize,
+    /// 无效 UTF-8 序列数，划分规则见 counter 模块
+    pub invalid: usize,
+    /// 以下三项是按语言的行分类，规则见 [`code`] 模块
+    pub code_lines: usize,
+    pub comment_lines: usize,
+    pub blank_lines: usize,
+    /// 解压前的字节数 (只在选择了 `decompress` 时统计；未压缩的输入等于 `bytes`)
+    pub compressed_bytes: u64,
+}
+
+// 合并两个输入的统计结果：最长行取最大值，其余计数相加
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Stats) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+        self.graphemes += other.graphemes;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+        self.invalid += other.invalid;
+        self.code_lines += other.code_lines;
+        self.comment_lines += other.comment_lines;
+        self.blank_lines += other.blank_lines;
+        self.compressed_bytes += other.compressed_bytes;
+    }
+}
+
+impl Add for Stats {
+    type Output = Stats;
+
+    fn add(mut self, other: Stats) -> Stats {
+        self += other;
+        self
+    }
+}
+
+impl Sum for Stats {
+    fn sum<I: Iterator<Item = Stats>>(iter: I) -> Stats {
+        iter.fold(Stats::default(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Stats> for Stats {
+    fn sum<I: Iterator<Item = &'a Stats>>(iter: I) -> Stats {
+        iter.copied().sum()
+    }
+}
+
+/// 统计器：用构建器方法选择要统计的项，然后统计任意多个输入。
+///
+/// 只统计选中的项，其余项在结果中为 0；只选择字节数时不解码输入。
+#[derive(Debug, Clone)]
+pub struct Counter {
+    lines: bool,
+    words: bool,
+    bytes: bool,
+    chars: bool,
+    graphemes: bool,
+    max_line_length: bool,
+    invalid: bool,
+    unicode_words: bool,
+    code_stats: bool,
+    decompress: Option<Decompress>,
+    mmap: bool,
+    jobs: usize,
+    line_terminator: u8,
+    record_separator: Option<Regex>,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+impl Counter {
+    /// 不选择任何统计项的统计器
+    pub fn new() -> Self {
+        Counter {
+            lines: false,
+            words: false,
+            bytes: false,
+            chars: false,
+            graphemes: false,
+            max_line_length: false,
+            invalid: false,
+            unicode_words: false,
+            code_stats: false,
+            decompress: None,
+            mmap: true,
+            jobs: 1,
+            line_terminator: b'\n',
+            record_separator: None,
+        }
+    }
+
+    pub fn lines(mut self, yes: bool) -> Self {
+        self.lines = yes;
+        self
+    }
+
+    pub fn words(mut self, yes: bool) -> Self {
+        self.words = yes;
+        self
+    }
+
+    pub fn bytes(mut self, yes: bool) -> Self {
+        self.bytes = yes;
+        self
+    }
+
+    pub fn chars(mut self, yes: bool) -> Self {
+        self.chars = yes;
+        self
+    }
+
+    pub fn graphemes(mut self, yes: bool) -> Self {
+        self.graphemes = yes;
+        self
+    }
+
+    pub fn max_line_length(mut self, yes: bool) -> Self {
+        self.max_line_length = yes;
+        self
+    }
+
+    pub fn invalid(mut self, yes: bool) -> Self {
+        self.invalid = yes;
+        self
+    }
+
+    /// 单词数按 UAX #29 单词边界统计，而不是按空白分词 (需要同时选择 `words`)
+    pub fn unicode_words(mut self, yes: bool) -> Self {
+        self.unicode_words = yes;
+        self
+    }
+
+    /// 按语言把行分为代码行、注释行和空行；`count_file` 按扩展名识别语言
+    pub fn code_stats(mut self, yes: bool) -> Self {
+        self.code_stats = yes;
+        self
+    }
+
+    /// 统计前先解压输入，并在 `compressed_bytes` 中记录解压前的字节数
+    pub fn decompress(mut self, mode: Option<Decompress>) -> Self {
+        self.decompress = mode;
+        self
+    }
+
+    /// `count_file` 是否把普通文件映射到内存中统计 (默认开启)；关闭时总是流式读取
+    pub fn mmap(mut self, yes: bool) -> Self {
+        self.mmap = yes;
+        self
+    }
+
+    /// `count_file` 对足够大的普通文件最多使用 `jobs` 个线程分块统计 (默认为 1)
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// 行结束符 (默认为 `b'\n'`)：行数统计这个字节，它也分隔单词并结束最长行的宽度计算。
+    ///
+    /// # Panics
+    ///
+    /// `terminator` 不是 ASCII 字节时 panic。
+    pub fn line_terminator(mut self, terminator: u8) -> Self {
+        assert!(terminator.is_ascii(), "行结束符必须是 ASCII 字节");
+        self.line_terminator = terminator;
+        self
+    }
+
+    /// 按正则表达式划分记录，行数改为统计记录数 (规则见 record 模块)；分隔符不能匹配空串
+    pub fn record_separator(mut self, separator: Option<Regex>) -> Self {
+        self.record_separator = separator;
+        self
+    }
+
+    /// 统计一个输入流
+    pub fn count_reader<R: Read>(&self, reader: R) -> io::Result<Stats> {
+        self.count(reader, None)
+    }
+
+    /// 统计一个输入流，并按 `language` 的语法对行分类 (需要选择 `code_stats`)
+    pub fn count_reader_as<R: Read>(&self, reader: R, language: Language) -> io::Result<Stats> {
+        self.count(reader, Some(language))
+    }
+
+    /// 打开并统计 `path` 指向的文件
+    pub fn count_file(&self, path: &Path) -> io::Result<Stats> {
+        self.count_open_file(File::open(path)?, path)
+    }
+
+    /// 统计一个已经打开的文件，`path` 用于识别语言和分块时重新打开文件
+    pub fn count_open_file(&self, file: File, path: &Path) -> io::Result<Stats> {
+        let language = self.language_of(path);
+        let Ok(metadata) = file.metadata() else {
+            return self.count(file, language);
+        };
+        // 解压时只能流式读取；大小为 0 的文件可能是 /proc 下实际有内容的文件
+        let mappable = self.mmap && self.decompress.is_none() && metadata.is_file() && metadata.len() > 0;
+        let mapped = if mappable { mapped::map(&file).ok() } else { None }; // 映射失败时退回流式读取
+        if let Some(data) = &mapped {
+            // 只需要行数和字节数：用向量化扫描统计换行符，不需要解码
+            let records = self.lines && self.record_separator.is_some();
+            if !(self.words || self.chars || self.graphemes || self.max_line_length || self.invalid || self.code_stats || records) {
+                let lines = if self.lines { mapped::count_byte(data, self.line_terminator) } else { 0 };
+                return Ok(self.select(Stats { lines, bytes: data.len(), ..Stats::default() }));
+            }
+        }
+        if metadata.is_file() && self.chunkable() && chunked::worth_chunking(metadata.len(), self.jobs) {
+            let stats = chunked::count_file_chunked(path, metadata.len(), self.jobs, self.line_terminator)?;
+            return Ok(self.select(stats));
+        }
+        if let Some(data) = &mapped {
+            let mut tally = self.tally(language);
+            for slice in data.chunks(mapped::SLICE_SIZE) {
+                tally.update(slice);
+            }
+            return Ok(tally.finish());
+        }
+        self.count(file, language)
+    }
+
+    /// 按扩展名识别 `path` 的语言；解压时忽略压缩格式的扩展名 (main.rs.gz 是 Rust 源文件)
+    pub fn language_of(&self, path: &Path) -> Option<Language> {
+        let is_compressed_name = |extension: &std::ffi::OsStr| {
+            [Compression::Gzip, Compression::Zstd, Compression::Xz, Compression::Bzip2]
+                .iter()
+                .any(|compression| compression.extensions().iter().any(|&ext| extension == ext))
+        };
+        match path.extension() {
+            Some(extension) if self.decompress.is_some() && is_compressed_name(extension) => {
+                Language::from_path(Path::new(path.file_stem()?))
+            }
+            _ => Language::from_path(path),
+        }
+    }
+
+    // 行分类、UAX #29 分段和记录分隔符的匹配依赖前面的内容，不能分块；解压后的内容也无法按字节范围分块
+    fn chunkable(&self) -> bool {
+        !(self.code_stats || self.needs_segmentation() || self.record_separator.is_some() || self.decompress.is_some())
+    }
+
+    fn needs_segmentation(&self) -> bool {
+        (self.words && self.unicode_words) || self.graphemes
+    }
+
+    /// 开始一次增量统计：输入分多次交给 [`Tally::update`]，随时可以取得当前结果。
+    /// `language` 用于按语言对行分类 (需要选择 `code_stats`)。
+    pub fn tally(&self, language: Option<Language>) -> Tally {
+        Tally {
+            counter: self.clone(),
+            bytes_only: !(self.lines || self.words || self.chars || self.graphemes || self.max_line_length
+                || self.invalid || self.code_stats),
+            bytes: 0,
+            stream: StreamCounter::with_terminator(self.line_terminator),
+            // 按语言的行分类、UAX #29 分段和记录划分只在需要时进行
+            code: language.filter(|_| self.code_stats).map(CodeCounter::new),
+            segment: self.needs_segmentation().then(SegmentCounter::new),
+            records: self.record_separator.clone().filter(|_| self.lines).map(RecordCounter::new),
+        }
+    }
+
+    fn count<R: Read>(&self, input: R, language: Option<Language>) -> io::Result<Stats> {
+        let Some(mode) = self.decompress else {
+            return self.count_decoded(input, language);
+        };
+        let (input, compressed_bytes) = decompress::CountingReader::new(input);
+        let (decoded, _) = decompress::wrap(input, mode)?;
+        let mut stats = self.count_decoded(decoded, language)?;
+        stats.compressed_bytes = compressed_bytes.get();
+        Ok(stats)
+    }
+
+    fn count_decoded<R: Read>(&self, input: R, language: Option<Language>) -> io::Result<Stats> {
+        let mut tally = self.tally(language);
+        for_each_chunk(input, |bytes| tally.update(bytes))?;
+        Ok(tally.finish())
+    }
+
+    /// 统计输入中各记号的出现次数和行长分布 (与选择的统计项无关)；设置了 `decompress` 时先解压
+    pub fn frequencies<R: Read>(&self, input: R, by: TokenKind) -> io::Result<Frequencies> {
+        let mut counter = FrequencyCounter::new(by);
+        match self.decompress {
+            Some(mode) => for_each_chunk(decompress::wrap(input, mode)?.0, |bytes| counter.update(bytes))?,
+            None => for_each_chunk(input, |bytes| counter.update(bytes))?,
+        }
+        Ok(counter.finish())
+    }
+
+    // 把没有选择的统计项清零
+    fn select(&self, stats: Stats) -> Stats {
+        let pick = |selected: bool, value: usize| if selected { value } else { 0 };
+        Stats {
+            lines: pick(self.lines, stats.lines),
+            words: pick(self.words, stats.words),
+            bytes: pick(self.bytes, stats.bytes),
+            chars: pick(self.chars, stats.chars),
+            graphemes: pick(self.graphemes, stats.graphemes),
+            max_line_length: pick(self.max_line_length, stats.max_line_length),
+            invalid: pick(self.invalid, stats.invalid),
+            code_lines: pick(self.code_stats, stats.code_lines),
+            comment_lines: pick(self.code_stats, stats.comment_lines),
+            blank_lines: pick(self.code_stats, stats.blank_lines),
+            compressed_bytes: if self.decompress.is_some() { stats.compressed_bytes } else { 0 },
+        }
+    }
+}
+
+/// 一次增量统计的状态，由 [`Counter::tally`] 创建。
+///
+/// 适合输入陆续到达的场景 (例如跟踪不断增长的日志文件)：每次只处理新到达的字节，
+/// 不需要从头重新读取。
+#[derive(Debug, Clone)]
+pub struct Tally {
+    counter: Counter,
+    bytes_only: bool, // 只需要字节数时不解码
+    bytes: usize,
+    stream: StreamCounter, // 行、词、字节、字符、最长行在同一次遍历中由状态机一起统计
+    code: Option<CodeCounter>,
+    segment: Option<SegmentCounter>,
+    records: Option<RecordCounter>,
+}
+
+impl Tally {
+    /// 处理紧接在之前所有输入之后的一段字节
+    pub fn update(&mut self, bytes: &[u8]) {
+        if self.bytes_only {
+            self.bytes += bytes.len();
+            return;
+        }
+        self.stream.update(bytes);
+        if let Some(code) = &mut self.code {
+            code.update(bytes);
+        }
+        if let Some(segment) = &mut self.segment {
+            segment.update(bytes);
+        }
+        if let Some(records) = &mut self.records {
+            records.update(bytes);
+        }
+    }
+
+    /// 到目前为止的统计结果，就像输入在这里结束一样 (不影响之后的 `update`)
+    pub fn snapshot(&self) -> Stats {
+        self.clone().finish()
+    }
+
+    /// 输入结束，返回最终结果
+    pub fn finish(self) -> Stats {
+        if self.bytes_only {
+            return self.counter.select(Stats { bytes: self.bytes, ..Stats::default() });
+        }
+        let mut stats = self.stream.finish();
+        if let Some(segment) = self.segment {
+            let (words, graphemes) = segment.finish();
+            if self.counter.unicode_words {
+                stats.words = words;
+            }
+            stats.graphemes = graphemes;
+        }
+        if let Some(code) = self.code {
+            code.finish(&mut stats);
+        }
+        if let Some(records) = self.records {
+            stats.lines = records.finish();
+        }
+        self.counter.select(stats)
+    }
+}
+
+// 读取下一块数据，遇到 `Interrupted` 时自动重试
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match reader.read(buf) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+// 以 8KB 的块读完整个输入，依次交给 `update`
+fn for_each_chunk<R: Read>(mut input: R, mut update: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut byte_buffer = [0; 8192]; // 8KB buffer
+    loop {
+        let n = read_chunk(&mut input, &mut byte_buffer)?;
+        if n == 0 { return Ok(()); }
+        update(&byte_buffer[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*; // 导入外部模块的项
+
+    // 辅助函数，用于从字符串创建模拟的 Read 输入
+    fn mock_reader_from_string(s: &str) -> impl Read {
+        io::Cursor::new(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_stats_calculation_lines() {
+        // 与 wc 一致，行数是换行符的个数：末尾没有换行符的最后一行不计入
+        let content = "line one\nline two\nline three";
+        let counter = Counter::new().lines(true);
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+        assert_eq!(stats.lines, 2);
+    }
+
+    #[test]
+    fn test_stats_calculation_words() {
+        let content = "word1 word2  word3\nword4"; // 注意双空格
+        let counter = Counter::new().words(true);
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+        assert_eq!(stats.words, 4);
+    }
+
+    #[test]
+    fn test_stats_calculation_bytes() {
+        let content = "byte test"; // 9 字节 (ASCII)
+        let counter = Counter::new().bytes(true);
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+        assert_eq!(stats.bytes, 9);
+
+        let content_utf8 = "你好"; // 6 字节 (UTF-8)
+        let stats_utf8 = counter.count_reader(mock_reader_from_string(content_utf8)).unwrap();
+        assert_eq!(stats_utf8.bytes, 6);
+    }
+
+    #[test]
+    fn test_stats_calculation_bytes_only_optimization() {
+        let content = "just bytes please";
+        let counter = Counter::new().bytes(true);
+        // 只请求字节时有优化，不解码内容
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+        assert_eq!(stats.bytes, content.len());
+    }
+
+
+    #[test]
+    fn test_stats_calculation_chars() {
+        let content_ascii = "char test"; // 9 字符
+        let counter = Counter::new().chars(true);
+        let stats_ascii = counter.count_reader(mock_reader_from_string(content_ascii)).unwrap();
+        assert_eq!(stats_ascii.chars, 9);
+
+        let content_utf8 = "你好世界"; // 4 字符
+        let stats_utf8 = counter.count_reader(mock_reader_from_string(content_utf8)).unwrap();
+        assert_eq!(stats_utf8.chars, 4);
+    }
+
+    #[test]
+    fn test_stats_calculation_all_options() {
+        let content = "hello world\n你好 Rustaceans\n";
+        // Expected:
+        // Bytes: "hello world" (11) + \n (1) + "你好 Rustaceans" (6 for 你好, 1 for space, 10 for Rustaceans) + \n (1) = 12 + 6 + 1 + 10 + 1 = 30
+        // Chars: "hello world" (11) + \n (1) + "你好 Rustaceans" (2 for 你好, 1 for space, 10 for Rustaceans) + \n (1) = 12 + 2 + 1 + 10 + 1 = 26
+        // Lines: 2 (wc 统计的是换行符的个数，"a\nb\n" 是 2，"a\nb" 是 1)
+        // Words: hello, world, 你好, Rustaceans -> 4
+        let counter = Counter::new().lines(true).words(true).bytes(true).chars(true);
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+
+        assert_eq!(stats.lines, 2, "Lines count mismatch");
+        assert_eq!(stats.words, 4, "Words count mismatch");
+        assert_eq!(stats.bytes, content.len(), "Bytes count mismatch"); // content.len() 是字节数
+        assert_eq!(stats.chars, 26, "Chars count mismatch");
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let content = "";
+        let counter = Counter::new().lines(true).words(true).bytes(true).chars(true);
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+        assert_eq!(stats.lines, 0); // "" -> 0 lines, " \n" -> 1 line
+        assert_eq!(stats.words, 0);
+        assert_eq!(stats.bytes, 0);
+        assert_eq!(stats.chars, 0);
+    }
+
+    #[test]
+    fn test_only_newlines() {
+        let content = "\n\n\n"; // 3 newlines -> 3 lines
+        let counter = Counter::new().lines(true).words(true).bytes(true).chars(true);
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+        assert_eq!(stats.lines, 3);
+        assert_eq!(stats.words, 0); // No words
+        assert_eq!(stats.bytes, 3);
+        assert_eq!(stats.chars, 3); // Newline is a char
+    }
+
+    #[test]
+    fn test_invalid_utf8_does_not_fail() {
+        // Latin-1 编码的 "café au lait\n"：é 是单个无效字节 0xE9
+        let content: &[u8] = b"caf\xe9 au lait\n";
+        let counter = Counter::new().lines(true).words(true).bytes(true).chars(true).invalid(true);
+        let stats = counter.count_reader(io::Cursor::new(content)).unwrap();
+        assert_eq!(stats.lines, 1);
+        assert_eq!(stats.words, 3);
+        assert_eq!(stats.bytes, content.len());
+        assert_eq!(stats.chars, 12); // 无效字节不计入字符数
+        assert_eq!(stats.invalid, 1);
+    }
+
+    #[test]
+    fn test_unicode_words_and_graphemes() {
+        let content = "hello world\n你好 Rustaceans\n";
+        // 按 UAX #29：hello, world, 你, 好, Rustaceans -> 5 个单词；没有组合字符时字素数等于字符数
+        let counter = Counter::new().words(true).chars(true).graphemes(true).unicode_words(true);
+        let stats = counter.count_reader(mock_reader_from_string(content)).unwrap();
+        assert_eq!(stats.words, 5);
+        assert_eq!(stats.chars, 26);
+        assert_eq!(stats.graphemes, 26);
+
+        // 不指定 --unicode-words 时仍按空白分词
+        let counter = Counter::new().words(true).graphemes(true);
+        let stats = counter.count_reader(mock_reader_from_string("e\u{301}te\u{301} 你好\r\n")).unwrap();
+        assert_eq!(stats.words, 2);
+        assert_eq!(stats.graphemes, 7); // é t é 空格 你 好 \r\n
+    }
+
+    #[test]
+    fn test_unselected_metrics_are_zero() {
+        let stats = Counter::new().words(true).count_reader(mock_reader_from_string("a b\nc\n")).unwrap();
+        assert_eq!(stats, Stats { words: 3, ..Stats::default() });
+    }
+
+    #[test]
+    fn test_code_stats_with_language() {
+        let counter = Counter::new().lines(true).code_stats(true);
+        let source = "// 注释\nfn main() {}\n\n";
+        let stats = counter.count_reader_as(mock_reader_from_string(source), Language::Rust).unwrap();
+        assert_eq!((stats.lines, stats.code_lines, stats.comment_lines, stats.blank_lines), (3, 1, 1, 1));
+        // 不指定语言时不分类
+        let stats = counter.count_reader(mock_reader_from_string(source)).unwrap();
+        assert_eq!((stats.lines, stats.code_lines), (3, 0));
+    }
+
+    #[test]
+    fn test_tally_snapshot_is_incremental() {
+        let counter = Counter::new().lines(true).words(true).chars(true).max_line_length(true);
+        let mut tally = counter.tally(None);
+        tally.update("hello wo".as_bytes());
+        assert_eq!(tally.snapshot(), Stats { words: 2, chars: 8, max_line_length: 8, ..Stats::default() });
+        tally.update("rld\n你好\n".as_bytes());
+        let expected = counter.count_reader(mock_reader_from_string("hello world\n你好\n")).unwrap();
+        assert_eq!(tally.snapshot(), expected);
+        assert_eq!(tally.finish(), expected);
+    }
+
+    #[test]
+    fn test_mmap_matches_streaming() {
+        use std::io::Write;
+        let mut content = "第一行 line one\n\tsecond line\n".repeat(5000).into_bytes();
+        content.extend_from_slice(b"\xff tail without newline");
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        temp_file.write_all(&content).unwrap();
+
+        let all = Counter::new().lines(true).words(true).bytes(true).chars(true).max_line_length(true).invalid(true);
+        for counter in [all, Counter::new().lines(true), Counter::new().lines(true).bytes(true), Counter::new().bytes(true)] {
+            let mapped = counter.count_file(temp_file.path()).unwrap();
+            assert_eq!(mapped, counter.clone().mmap(false).count_file(temp_file.path()).unwrap());
+            assert_eq!(mapped, counter.count_reader(io::Cursor::new(&content)).unwrap());
+        }
+        // 空文件不映射
+        let empty = tempfile::NamedTempFile::new().unwrap();
+        assert_eq!(Counter::new().lines(true).bytes(true).count_file(empty.path()).unwrap(), Stats::default());
+    }
+
+    #[test]
+    fn test_line_terminator_and_records() {
+        use std::io::Write;
+        // 大到足以分块并行统计，每条记录以 NUL 结束
+        let content = "第一条 record\n含换行\0second\0".repeat(200_000).into_bytes();
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        temp_file.write_all(&content).unwrap();
+
+        let nul = Counter::new().lines(true).words(true).line_terminator(b'\0');
+        let expected = Stats { lines: 400_000, words: 800_000, ..Stats::default() };
+        assert_eq!(nul.count_reader(io::Cursor::new(&content)).unwrap(), expected);
+        assert_eq!(nul.clone().jobs(4).count_file(temp_file.path()).unwrap(), expected);
+        let lines_only = Counter::new().lines(true).line_terminator(b'\0');
+        assert_eq!(lines_only.count_file(temp_file.path()).unwrap().lines, 400_000);
+
+        // 记录数代替行数；最后一条没有分隔符的记录也计入
+        let records = Counter::new().lines(true).bytes(true).record_separator(Some(Regex::new(r"\n\n+").unwrap()));
+        let stats = records.count_reader(mock_reader_from_string("a\nb\n\nc\n\n\nd\n")).unwrap();
+        assert_eq!((stats.lines, stats.bytes), (3, 11));
+        assert_eq!(records.clone().jobs(4).count_file(temp_file.path()).unwrap().lines, 1);
+    }
+
+    #[test]
+    fn test_stats_add_and_sum() {
+        let a = Stats { lines: 1, words: 2, max_line_length: 10, ..Stats::default() };
+        let b = Stats { lines: 3, words: 4, max_line_length: 7, ..Stats::default() };
+        let expected = Stats { lines: 4, words: 6, max_line_length: 10, ..Stats::default() };
+        assert_eq!(a + b, expected);
+        assert_eq!([a, b].iter().sum::<Stats>(), expected);
+        assert_eq!([a, b].into_iter().sum::<Stats>(), expected);
+        let mut total = Stats::default();
+        total += a;
+        total += b;
+        assert_eq!(total, expected);
+    }
+}
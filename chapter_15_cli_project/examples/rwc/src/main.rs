@@ -1,8 +1,27 @@
-use clap::Parser;
-use anyhow::{Context, Result}; // anyhow::Result 用于简化错误处理
-use std::fs::File;
-use std::io::{self, Read, BufReader, BufRead}; // 引入所需的 io traits 和类型
-use std::path::PathBuf;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use anyhow::Result; // anyhow::Result 用于简化错误处理
+use regex::bytes::Regex;
+use rwc::{parallel, Counter, Decompress, Language, Stats, TokenKind};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read}; // 引入所需的 io traits 和类型
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+// 命令行前端：参数解析、输入展开和输出格式在这里，统计本身由 rwc 库完成
+mod config;
+mod diff;
+mod follow;
+mod generate;
+mod output;
+mod status;
+mod top;
+mod walk;
+
+use config::{Config, Width};
+use output::{Format, Reporter};
+use status::{Failures, Stop};
 
 // 使用 clap derive 宏来定义命令行参数
 #[derive(Parser, Debug)]
@@ -11,13 +30,19 @@ use std::path::PathBuf;
     version = "0.1.0", // 版本
     author = "Jules The AI Assistant", // 作者
     about = "Rust 版本的 wc (word count) 工具", // 程序简介
-    long_about = "一个简单的命令行工具，用于统计文件或标准输入中的行数、单词数、字节数和字符数。"
+    long_about = "一个简单的命令行工具，用于统计文件或标准输入中的行数、单词数、字节数和字符数。\n\
+                  计数规则、列宽和选项与 GNU wc 兼容，可以直接替代 wc 使用。",
+    args_conflicts_with_subcommands = true, // 子命令有自己的选项 (名为 diff 的文件可以写成 ./diff)
+    disable_help_subcommand = true // `rwc help` 统计名为 help 的文件，帮助用 --help
 )]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(
         short = 'l',
         long,
-        help = "打印行数 (lines)"
+        help = "打印行数 (lines，即换行符的个数)"
     )]
     lines: bool,
 
@@ -42,258 +67,539 @@ struct Cli {
     )]
     chars: bool,
 
+    #[clap(
+        long,
+        help = "打印字素数 (用户感知的字符，UAX #29)"
+    )]
+    graphemes: bool,
+
+    #[clap(
+        long,
+        help = "按 UAX #29 单词边界统计单词数 (每个汉字都是一个单词，标点不是单词)，而不是按空白分词"
+    )]
+    unicode_words: bool,
+
+    #[clap(
+        short = 'L',
+        long,
+        help = "打印最长行的显示宽度 (max line length)"
+    )]
+    max_line_length: bool,
+
+    #[clap(
+        long,
+        help = "打印无效 UTF-8 序列数 (无效序列不计入字符数)"
+    )]
+    invalid: bool,
+
+    #[clap(
+        long,
+        help = "按扩展名识别语言 (Rust、C/C++、Python、Shell)，统计代码行、注释行和空行，并在总计后输出按语言汇总的表格"
+    )]
+    code_stats: bool,
+
+    #[clap(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto",
+        conflicts_with = "follow",
+        help = "统计前先解压输入：auto (默认，按魔数识别，未压缩的输入按原样统计)、gzip、zstd、xz 或 bzip2；同时输出压缩数据的字节数"
+    )]
+    decompress: Option<Decompress>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["follow", "code_stats"],
+        help = "频率分析：打印每个文件和总计中出现最多的 N 个记号 (见 --by) 以及行长分布，代替计数"
+    )]
+    top: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "TOKENS",
+        default_value = "words",
+        requires = "top",
+        help = "--top 统计的记号：words (以空白分隔的单词)、chars (非空白字符) 或 bigrams (相邻的两个单词)"
+    )]
+    by: TokenKind,
+
+    #[clap(
+        long,
+        value_name = "BYTE",
+        value_parser = parse_line_terminator,
+        conflicts_with_all = ["zero_terminated", "record_separator", "code_stats", "top"],
+        help = "以 BYTE 作为行结束符：单个 ASCII 字符或转义序列 (\\n、\\r、\\t、\\0、\\\\、\\xHH)。行数统计这个字节，它也分隔单词并结束 -L 的行"
+    )]
+    line_terminator: Option<u8>,
+
+    #[clap(
+        short = 'z',
+        long,
+        conflicts_with_all = ["record_separator", "code_stats", "top"],
+        help = "以 NUL 字符作为行结束符，等同于 --line-terminator '\\0'"
+    )]
+    zero_terminated: bool,
+
+    #[clap(
+        long,
+        value_name = "REGEX",
+        value_parser = parse_record_separator,
+        conflicts_with_all = ["code_stats", "top"],
+        help = "按正则表达式划分记录，行数一列改为记录数 (最后一个分隔符之后的内容也算一条记录)"
+    )]
+    record_separator: Option<Regex>,
+
+    #[clap(
+        long,
+        value_name = "F",
+        conflicts_with = "FILE",
+        help = "从文件 F 中读取以 NUL 字符分隔的输入文件名 (F 为 - 时从 stdin 读取)"
+    )]
+    files0_from: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        default_value_t = TotalMode::Auto,
+        help = "何时打印总计行：auto (多于一个输入时)、always、only (只打印总计，不带 total 标签)、never"
+    )]
+    total: TotalMode,
+
+    #[clap(
+        short = 'r',
+        long,
+        help = "递归统计目录参数中的所有文件 (按文件名排序)"
+    )]
+    recursive: bool,
+
+    #[clap(
+        long,
+        value_name = "GLOB",
+        value_parser = walk::parse_glob,
+        requires = "recursive",
+        help = "递归时只统计匹配 GLOB 的文件 (与相对路径或文件名匹配，可多次指定)"
+    )]
+    include: Vec<globset::Glob>,
+
+    #[clap(
+        long,
+        value_name = "GLOB",
+        value_parser = walk::parse_glob,
+        requires = "recursive",
+        help = "递归时跳过匹配 GLOB 的文件和目录 (与相对路径或文件名匹配，可多次指定)"
+    )]
+    exclude: Vec<globset::Glob>,
+
+    #[clap(
+        long,
+        requires = "recursive",
+        help = "递归时遵循 .gitignore 文件并跳过 .git 目录"
+    )]
+    gitignore: bool,
+
+    #[clap(
+        long,
+        requires = "FILE",
+        conflicts_with = "files0_from",
+        help = "持续跟踪文件的增长 (包括截断和轮转)，每隔 --interval 秒重新输出计数表，直到被中断"
+    )]
+    follow: bool,
+
+    #[clap(
+        long,
+        value_name = "SECS",
+        default_value = "1",
+        value_parser = follow::parse_interval,
+        requires = "follow",
+        help = "--follow 时检查文件和重新输出的间隔秒数 (可以是小数)"
+    )]
+    interval: std::time::Duration,
+
+    #[clap(
+        short = 'j',
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        help = "使用 N 个线程并行统计 (0 表示使用全部 CPU)：多个文件时按文件并行且输出顺序与参数顺序一致，单个大文件时按字节范围分块并行"
+    )]
+    jobs: usize,
+
+    #[clap(
+        long,
+        help = "不把普通文件映射到内存，总是按块流式读取 (例如文件可能在统计过程中被截断时)"
+    )]
+    no_mmap: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "follow",
+        help = "遇到第一个无法统计的输入时立即停止 (不输出总计)，而不是报告错误后继续处理其他输入"
+    )]
+    strict: bool,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = Format::Table,
+        help = "输出格式：table 为与 wc 相同的对齐列，json/csv/tsv 为带稳定字段名的机器可读格式"
+    )]
+    format: Format,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "表格中每列的最小宽度 (auto 表示按 GNU wc 的规则计算)"
+    )]
+    width: Option<Width>,
+
+    #[clap(
+        long,
+        help = "打印合并命令行、环境变量 (RWC_METRICS、RWC_FORMAT、RWC_WIDTH) 和配置文件之后的有效设置，然后退出"
+    )]
+    print_config: bool,
+
     // value_parser 用于 PathBuf，可以更好地处理路径
-    #[clap(value_parser = clap::value_parser!(PathBuf), name = "FILE", help = "输入文件列表 (如果为空，则从 stdin 读取)")]
+    #[clap(value_parser = clap::value_parser!(PathBuf), name = "FILE", help = "输入文件列表 (如果为空或为 -，则从 stdin 读取)")]
     files: Vec<PathBuf>, // 接收一个或多个文件名/路径
 }
 
-// 用于存储统计结果的结构体
-#[derive(Debug, Default, Clone, Copy)] // Default 用于方便初始化，Copy 用于多文件总计
-struct Stats {
-    lines: usize,
-    words: usize,
-    bytes: usize,
-    chars: usize,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 比较两个目录、两个文件列表或两个 git 版本中各文件的行、词、字节数的变化
+    Diff(diff::DiffArgs),
+    /// 输出指定 shell 的补全脚本
+    Completions {
+        #[clap(value_enum, help = "目标 shell")]
+        shell: clap_complete::Shell,
+    },
+    /// 输出 man 手册 (roff 格式)
+    Man,
 }
 
-fn main() -> Result<()> { // main 函数返回 anyhow::Result 以便使用 `?`
-    let mut cli_args = Cli::parse();
+// --total 的取值，与 GNU wc 相同
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TotalMode {
+    #[default]
+    Auto,
+    Always,
+    Only,
+    Never,
+}
 
-    // 如果没有指定任何统计选项 (-l, -w, -c, -m)，则默认显示行、词、字节
-    let no_options_specified = !(cli_args.lines || cli_args.words || cli_args.bytes || cli_args.chars);
-    if no_options_specified {
-        cli_args.lines = true;
-        cli_args.words = true;
-        cli_args.bytes = true;
+// 一个待统计的输入
+#[derive(Debug)]
+enum Input {
+    Stdin, // 没有文件参数，或文件名为 "-"
+    Path(PathBuf),
+    Invalid(String), // --files0-from 中的非法文件名，轮到它时报告这条错误信息
+}
+
+impl Input {
+    fn from_path(path: PathBuf) -> Input {
+        if path.as_os_str() == "-" { Input::Stdin } else { Input::Path(path) }
     }
 
-    if cli_args.files.is_empty() {
-        // 从标准输入读取
-        // println!("从 stdin 读取..."); // 调试信息
-        let stdin_stats = process_input(io::stdin(), &cli_args, "-")?;
-        print_stats(&stdin_stats, &cli_args, "-");
-    } else {
-        let mut total_stats = Stats::default();
-        let num_files = cli_args.files.len();
-
-        for filepath in &cli_args.files {
-            let filename_str = filepath.to_string_lossy(); // 处理非 UTF-8 路径名
-            // println!("处理文件: {}", filename_str); // 调试信息
-            match File::open(filepath) {
-                Ok(file) => {
-                    let file_stats = process_input(file, &cli_args, &filename_str)?;
-                    print_stats(&file_stats, &cli_args, &filename_str);
-                    if num_files > 1 {
-                        total_stats.lines += file_stats.lines;
-                        total_stats.words += file_stats.words;
-                        total_stats.bytes += file_stats.bytes;
-                        total_stats.chars += file_stats.chars;
-                    }
-                }
-                Err(e) => {
-                    // 使用 eprintln! 将错误信息打印到 stderr
-                    eprintln!("rwc: {}: {}", filename_str, e);
-                    // 可以在这里选择是否继续处理其他文件，或者直接 panic/exit
-                    // 对于 wc 行为，通常会打印错误并继续
-                }
-            }
+    fn name(&self) -> String {
+        match self {
+            Input::Stdin => "-".to_string(),
+            Input::Path(path) => path.to_string_lossy().into_owned(), // 处理非 UTF-8 路径名
+            Input::Invalid(_) => String::new(),
         }
+    }
 
-        if num_files > 1 {
-            print_stats(&total_stats, &cli_args, "total");
+    fn metadata(&self) -> io::Result<fs::Metadata> {
+        match self {
+            Input::Stdin => stdin_metadata(),
+            Input::Path(path) => fs::metadata(path),
+            Input::Invalid(message) => Err(io::Error::new(io::ErrorKind::InvalidInput, message.clone())),
         }
     }
-
-    Ok(())
 }
 
-// 处理输入源 (可以是文件或标准输入) 并返回统计结果
-// R: Read 泛型参数允许我们传入 File 或 Stdin
-fn process_input<R: Read>(input_source: R, cli: &Cli, source_name: &str) -> Result<Stats> {
-    let mut reader = BufReader::new(input_source);
-    let mut stats = Stats::default();
-    let mut buffer = String::new(); // 用于存储所有内容以计算字符和单词
-
-    // 为了分别计算字节和字符/单词/行，我们可能需要读取两次或更智能地处理
-    // 简单起见，先读取所有内容到 String，这对于非常大的文件不理想
-    // 但对于 wc 的常见用例（统计字节、行、词、字符）通常需要完整内容
-
-    // 如果只需要字节数，可以流式读取并计数
-    if cli.bytes && !(cli.lines || cli.words || cli.chars) { // 仅字节数优化
-        let mut byte_buffer = [0; 8192]; // 8KB buffer
-        loop {
-            let n = reader.read(&mut byte_buffer)
-                .with_context(|| format!("读取 '{}' 时发生错误", source_name))?;
-            if n == 0 { break; }
-            stats.bytes += n;
+// 退出码的约定见 status.rs：用法错误由 clap 以 2 退出，其余错误以 1 退出
+fn main() -> ExitCode {
+    // 命令行参数优先，没有给出的设置由配置文件和环境变量补充 (见 config.rs)
+    let matches = Cli::command().get_matches();
+    let mut cli_args = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // 生成补全脚本和手册页不使用这些设置，配置有误时也可以运行
+    if !matches!(cli_args.command, Some(Command::Completions { .. } | Command::Man)) {
+        let config = Config::load().unwrap_or_else(|message| Cli::command().error(ErrorKind::InvalidValue, message).exit());
+        if cli_args.print_config {
+            return match config.print(io::stdout().lock(), &cli_args, &matches) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(_) => ExitCode::from(status::FAILURE),
+            };
         }
-        return Ok(stats); // 如果只计算字节，提前返回
-    }
-
-    // 对于行、词、字符，需要读取到 String
-    reader.read_to_string(&mut buffer)
-        .with_context(|| format!("读取 '{}' 内容到字符串时发生错误", source_name))?;
-
-    // 字节数可以直接从 String 的字节长度获取
-    if cli.bytes || cli.lines || cli.words || cli.chars { // 如果需要任何非纯字节统计
-        stats.bytes = buffer.len(); // String.len() 返回字节数
+        config.apply(&mut cli_args, &matches);
     }
 
-
-    if cli.lines {
-        stats.lines = buffer.lines().count();
+    match run(cli_args) {
+        Ok(code) => code,
+        Err(e) => {
+            // 输出被提前关闭 (例如 `rwc * | head -1`) 时安静地退出
+            let broken_pipe = e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe);
+            if !broken_pipe {
+                eprintln!("rwc: {:#}", e);
+            }
+            ExitCode::from(status::FAILURE)
+        }
     }
+}
 
-    if cli.words {
-        stats.words = buffer.split_whitespace().count();
+fn run(mut cli_args: Cli) -> Result<ExitCode> { // 返回 anyhow::Result 以便使用 `?`
+    match &cli_args.command {
+        Some(Command::Diff(args)) => return diff::run(args),
+        Some(Command::Completions { shell }) => return generate::completions(*shell),
+        Some(Command::Man) => return generate::man(),
+        None => {}
     }
 
-    if cli.chars {
-        stats.chars = buffer.chars().count();
+    // 如果没有指定任何统计选项 (-l, -w, -c, -m, -L)，则默认显示行、词、字节
+    let no_options_specified = !(cli_args.lines || cli_args.words || cli_args.bytes || cli_args.chars || cli_args.graphemes
+        || cli_args.max_line_length || cli_args.invalid);
+    if no_options_specified {
+        cli_args.lines = true;
+        cli_args.words = true;
+        cli_args.bytes = true;
     }
 
-    Ok(stats)
-}
-
-// 打印统计结果
-fn print_stats(stats: &Stats, cli: &Cli, filename: &str) {
-    let mut output_parts = Vec::new();
-
-    if cli.lines {
-        output_parts.push(format!("{:>7}", stats.lines)); // 右对齐，宽度7
-    }
-    if cli.words {
-        output_parts.push(format!("{:>7}", stats.words));
+    // 确定输入列表：--files0-from、文件参数，或者 stdin
+    let (inputs, names_streamed) = match &cli_args.files0_from {
+        Some(list) => match read_files0_from(list) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("rwc: cannot open '{}' for reading: {}", list.display(), io_error_message(&e));
+                return Ok(ExitCode::from(status::FAILURE));
+            }
+        },
+        None if cli_args.files.is_empty() => (vec![Input::Stdin], false),
+        None => (cli_args.files.iter().cloned().map(Input::from_path).collect(), false),
+    };
+    // --recursive 时把目录参数展开为其中的文件，按文件汇总到各行和总计中
+    let inputs = if cli_args.recursive {
+        walk::Filter::new(&cli_args.include, &cli_args.exclude, cli_args.gitignore)?.expand(inputs)
+    } else {
+        inputs
+    };
+    if cli_args.follow {
+        follow::run(&cli_args, inputs)?;
+        return Ok(ExitCode::SUCCESS);
     }
-    if cli.bytes { // 默认行为包含字节，或显式指定 -c
-        output_parts.push(format!("{:>7}", stats.bytes));
+    // 没有文件参数、直接读取 stdin 时，输出中不带文件名
+    let show_names = cli_args.files0_from.is_some() || !cli_args.files.is_empty();
+    if let Some(n) = cli_args.top {
+        return top::run(&cli_args, n, inputs, show_names);
     }
-    if cli.chars { // 只有显式指定 -m 时才打印字符数
-        output_parts.push(format!("{:>7}", stats.chars));
+
+    let fields = output::requested_fields(&cli_args);
+    let width = table_width(&cli_args, &inputs, fields.len(), names_streamed);
+    let mut reporter = Reporter::new(io::stdout().lock(), cli_args.format, fields, width, cli_args.total);
+
+    let mut total_stats = Stats::default();
+    let mut languages: BTreeMap<Language, (usize, Stats)> = BTreeMap::new(); // --code-stats：每种语言的文件数和汇总
+    // 与 wc 一致：有输入出错时继续处理其他输入，但最终以非 0 退出 (--strict 时立即停止)
+    let mut failures = Failures::new(cli_args.strict);
+
+    // 各输入在工作线程上统计，结果按参数顺序交回这里打印和累加，
+    // 所以无论 --jobs 取何值，输出都与顺序处理时相同
+    let jobs = parallel::effective_jobs(cli_args.jobs);
+    // 只有一个输入时，把线程都用于对这个文件分块统计
+    let counter = counter_from_cli(&cli_args).jobs(if inputs.len() == 1 { jobs } else { 1 });
+    // 打开失败 (外层错误) 时不输出该输入的计数行，读取失败 (内层错误) 时输出一行 0
+    let result = parallel::for_each_ordered(
+        &inputs,
+        jobs,
+        |input| match input {
+            Input::Stdin => Ok(counter.count_reader(io::stdin())),
+            Input::Path(filepath) => File::open(filepath).map(|file| counter.count_open_file(file, filepath)),
+            Input::Invalid(message) => Err(io::Error::new(io::ErrorKind::InvalidInput, message.clone())),
+        },
+        |index, outcome| -> Result<(), Stop> {
+            let input = &inputs[index];
+            let name = show_names.then(|| input.name());
+            match outcome {
+                Ok(Ok(file_stats)) => {
+                    reporter.file(name.as_deref(), &file_stats)?;
+                    total_stats += file_stats;
+                    if let Input::Path(path) = input {
+                        if let Some(language) = counter.language_of(path).filter(|_| cli_args.code_stats) {
+                            let (files, language_stats) = languages.entry(language).or_default();
+                            *files += 1;
+                            *language_stats += file_stats;
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    // 读取失败 (例如目录)：与 wc 一致，报告错误后仍然输出该输入的一行计数
+                    failures.record(input, &e)?;
+                    reporter.file(name.as_deref(), &Stats::default())?;
+                }
+                // 打开失败：报告错误后继续处理其他文件
+                Err(e) => failures.record(input, &e)?,
+            }
+            Ok(())
+        },
+    );
+    if status::stopped(result)? {
+        return Ok(failures.exit_code(inputs.len()));
     }
 
-    // 如果什么选项都没指定，但我们走到了这里（意味着 files 非空，或者 stdin）
-    // 并且 print_stats 被调用，我们应该打印默认的 行、词、字节
-    // 这个逻辑已在 main 中处理，决定哪些字段被计算并传递给 cli
-    // 这里只根据 cli 中为 true 的字段打印
-
-    if !output_parts.is_empty() {
-        print!("{}", output_parts.join(" "));
-        if filename != "-" || !cli.files.is_empty() { // 如果不是来自 stdin 且没有文件名，或者有文件名
-            println!(" {}", filename);
-        } else {
-            println!(); // 对于 stdin 且无文件名的情况，只打印数字
-        }
-    } else if filename == "-" && cli.files.is_empty() && output_parts.is_empty() {
-        // 如果是 stdin，没有指定任何选项，并且没有文件，
-        // 默认行为（行、词、字节）应该已经被 cli 结构体设置了。
-        // 如果 output_parts 仍然为空，说明没有任何统计被请求，这不应该发生除非逻辑错误。
-        // 但为了安全，如果没有任何输出部分，且是 stdin，也打印一个换行。
-        // 实际上，如果 options_specified 为 false，cli.lines/words/bytes 会被设为 true。
-        // 所以 output_parts 不会为空。
-        // println!(); // 这个分支可能永远不会到达
+    if cli_args.code_stats {
+        reporter.languages(
+            languages
+                .into_iter()
+                .map(|(language, (files, stats))| output::LanguageSummary { name: language.name(), files, stats })
+                .collect(),
+        );
     }
+    reporter.finish(&total_stats, inputs.len())?;
+    Ok(failures.exit_code(inputs.len()))
 }
 
-// 可以在这里添加单元测试模块
-#[cfg(test)]
-mod tests {
-    use super::*; // 导入外部模块的项
-
-    // 辅助函数，用于从字符串创建模拟的 Read 输入
-    fn mock_reader_from_string(s: &str) -> impl Read {
-        io::Cursor::new(s.as_bytes().to_vec())
-    }
+// 按命令行选择的统计项创建统计器
+fn counter_from_cli(cli: &Cli) -> Counter {
+    Counter::new()
+        .lines(cli.lines)
+        .words(cli.words)
+        .bytes(cli.bytes)
+        .chars(cli.chars)
+        .graphemes(cli.graphemes)
+        .max_line_length(cli.max_line_length)
+        .invalid(cli.invalid)
+        .unicode_words(cli.unicode_words)
+        .code_stats(cli.code_stats)
+        .decompress(cli.decompress)
+        .mmap(!cli.no_mmap)
+        .line_terminator(if cli.zero_terminated { b'\0' } else { cli.line_terminator.unwrap_or(b'\n') })
+        .record_separator(cli.record_separator.clone())
+}
 
-    #[test]
-    fn test_stats_calculation_lines() {
-        let content = "line one\nline two\nline three";
-        let cli = Cli { lines: true, words: false, bytes: false, chars: false, files: vec![] };
-        let stats = process_input(mock_reader_from_string(content), &cli, "test").unwrap();
-        assert_eq!(stats.lines, 3);
+// --line-terminator 的参数解析器：单个 ASCII 字符，或者 \n、\r、\t、\0、\\、\xHH 形式的转义序列
+fn parse_line_terminator(value: &str) -> Result<u8, String> {
+    let byte = match value.as_bytes() {
+        [byte] => *byte,
+        [b'\\', b'n'] => b'\n',
+        [b'\\', b'r'] => b'\r',
+        [b'\\', b't'] => b'\t',
+        [b'\\', b'0'] => b'\0',
+        [b'\\', b'\\'] => b'\\',
+        [b'\\', b'x', hex @ ..] if hex.len() == 2 => std::str::from_utf8(hex)
+            .ok()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| format!("无效的转义序列 '{}'", value))?,
+        _ if value.chars().count() == 1 => u8::MAX, // 非 ASCII 字符，下面报错
+        _ => return Err(format!("行结束符必须是单个字符或转义序列，而不是 '{}'", value)),
+    };
+    if !byte.is_ascii() {
+        return Err(format!("行结束符必须是 ASCII 字节，而不是 '{}'", value));
     }
+    Ok(byte)
+}
 
-    #[test]
-    fn test_stats_calculation_words() {
-        let content = "word1 word2  word3\nword4"; // 注意双空格
-        let cli = Cli { lines: false, words: true, bytes: false, chars: false, files: vec![] };
-        let stats = process_input(mock_reader_from_string(content), &cli, "test").unwrap();
-        assert_eq!(stats.words, 4);
+// --record-separator 的参数解析器：能匹配空串的分隔符会把每个位置都当作记录边界，不接受
+fn parse_record_separator(value: &str) -> Result<Regex, String> {
+    let separator = Regex::new(value).map_err(|e| e.to_string())?;
+    if separator.is_match(b"") {
+        return Err(format!("记录分隔符 '{}' 能匹配空串", value));
     }
+    Ok(separator)
+}
 
-    #[test]
-    fn test_stats_calculation_bytes() {
-        let content = "byte test"; // 9 字节 (ASCII)
-        let cli = Cli { lines: false, words: false, bytes: true, chars: false, files: vec![] };
-        let stats = process_input(mock_reader_from_string(content), &cli, "test").unwrap();
-        assert_eq!(stats.bytes, 9);
+// 读取 --files0-from 指定的文件名列表。
+// 返回的布尔值表示列表是否来自管道等非普通文件：GNU wc 此时逐个读取文件名，
+// 无法预先得知所有文件的大小，因此不对齐列宽。
+fn read_files0_from(list: &Path) -> io::Result<(Vec<Input>, bool)> {
+    let from_stdin = list.as_os_str() == "-";
+    let (content, metadata) = if from_stdin {
+        let mut content = Vec::new();
+        io::stdin().lock().read_to_end(&mut content)?;
+        (content, stdin_metadata())
+    } else {
+        (fs::read(list)?, fs::metadata(list))
+    };
+    let names_streamed = !metadata.is_ok_and(|m| m.is_file());
 
-        let content_utf8 = "你好"; // 6 字节 (UTF-8)
-        let stats_utf8 = process_input(mock_reader_from_string(content_utf8), &cli, "test_utf8").unwrap();
-        assert_eq!(stats_utf8.bytes, 6);
+    let mut names: Vec<&[u8]> = content.split(|&b| b == 0).collect();
+    if names.last().is_some_and(|name| name.is_empty()) {
+        names.pop(); // 最后一个 NUL 之后的空串不是文件名
     }
+    let inputs = names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if name.is_empty() {
+                Input::Invalid(format!("{}:{}: invalid zero-length file name", list.display(), i + 1))
+            } else if from_stdin && name == b"-" {
+                Input::Invalid("when reading file names from stdin, no file name of '-' allowed".to_string())
+            } else {
+                Input::from_path(path_from_bytes(name))
+            }
+        })
+        .collect();
+    Ok((inputs, names_streamed))
+}
 
-    #[test]
-    fn test_stats_calculation_bytes_only_optimization() {
-        let content = "just bytes please";
-        let cli = Cli { lines: false, words: false, bytes: true, chars: false, files: vec![] };
-        // process_input 内部有优化，如果只请求字节，会流式读取
-        let stats = process_input(mock_reader_from_string(content), &cli, "test_bytes_only").unwrap();
-        assert_eq!(stats.bytes, content.len());
-    }
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
 
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
 
-    #[test]
-    fn test_stats_calculation_chars() {
-        let content_ascii = "char test"; // 9 字符
-        let cli = Cli { lines: false, words: false, bytes: false, chars: true, files: vec![] };
-        let stats_ascii = process_input(mock_reader_from_string(content_ascii), &cli, "test_ascii").unwrap();
-        assert_eq!(stats_ascii.chars, 9);
+#[cfg(unix)]
+fn stdin_metadata() -> io::Result<fs::Metadata> {
+    use std::os::fd::AsFd;
+    File::from(io::stdin().as_fd().try_clone_to_owned()?).metadata()
+}
 
-        let content_utf8 = "你好世界"; // 4 字符
-        let stats_utf8 = process_input(mock_reader_from_string(content_utf8), &cli, "test_utf8").unwrap();
-        assert_eq!(stats_utf8.chars, 4);
-    }
+#[cfg(not(unix))]
+fn stdin_metadata() -> io::Result<fs::Metadata> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "无法获取 stdin 的元数据"))
+}
 
-    #[test]
-    fn test_stats_calculation_all_options() {
-        let content = "hello world\n你好 Rustaceans\n";
-        // Expected:
-        // Bytes: "hello world" (11) + \n (1) + "你好 Rustaceans" (18 for 你好, 1 for space, 9 for Rustaceans) + \n (1) = 12 + 18 + 1 + 1 = 32
-        // Chars: "hello world" (11) + \n (1) + "你好 Rustaceans" (2 for 你好, 1 for space, 9 for Rustaceans) + \n (1) = 12 + 2 + 1 + 9 + 1 = 25
-        // Lines: 2 (因为末尾有换行符，所以是2行，如果末尾没有换行，lines().count() 会少1，但 wc 行为是算最后一个换行符)
-        //        实际上，`buffer.lines().count()` 对于 "a\nb\n" 是 2，对于 "a\nb" 是 2。
-        //        对于 `wc` 来说，如果文件末尾没有换行符，最后一行也算一行。
-        //        `buffer.lines().count()` 会将末尾无换行符的最后一行也计入。
-        // Words: hello, world, 你好, Rustaceans -> 4
-        let cli = Cli { lines: true, words: true, bytes: true, chars: true, files: vec![] };
-        let stats = process_input(mock_reader_from_string(content), &cli, "test_all").unwrap();
-
-        assert_eq!(stats.lines, 2, "Lines count mismatch");
-        assert_eq!(stats.words, 4, "Words count mismatch");
-        assert_eq!(stats.bytes, content.len(), "Bytes count mismatch"); // content.len() 是字节数
-        assert_eq!(stats.chars, 25, "Chars count mismatch");
+// --width (或配置) 指定了固定的列宽时使用它，否则按 GNU wc 的规则计算
+fn table_width(cli: &Cli, inputs: &[Input], field_count: usize, names_streamed: bool) -> usize {
+    match cli.width {
+        Some(Width::Fixed(width)) => width.get(),
+        Some(Width::Auto) | None => number_width(inputs, field_count, names_streamed),
     }
+}
 
-    #[test]
-    fn test_empty_input() {
-        let content = "";
-        let cli = Cli { lines: true, words: true, bytes: true, chars: true, files: vec![] };
-        let stats = process_input(mock_reader_from_string(content), &cli, "test_empty").unwrap();
-        assert_eq!(stats.lines, 0); // "" -> 0 lines, " \n" -> 1 line
-        assert_eq!(stats.words, 0);
-        assert_eq!(stats.bytes, 0);
-        assert_eq!(stats.chars, 0);
+// 与 GNU wc 相同的列宽计算：
+// - 只有一个输入且只打印一项计数时不填充 (宽度 1)；
+// - 否则宽度为所有普通文件大小之和的位数；
+// - 有 stdin、管道、目录等非普通文件时宽度至少为 7 (无法预先知道其大小)；
+// - 文件名是逐个读取的 (--files0-from 指定管道等非普通文件) 时宽度为 1。
+fn number_width(inputs: &[Input], field_count: usize, names_streamed: bool) -> usize {
+    if names_streamed || inputs.is_empty() || (inputs.len() == 1 && field_count == 1) {
+        return 1;
+    }
+    let mut minimum_width = 1;
+    let mut regular_total: u64 = 0;
+    for input in inputs {
+        match input.metadata() {
+            Ok(metadata) if metadata.is_file() => regular_total = regular_total.saturating_add(metadata.len()),
+            Ok(_) => minimum_width = 7,
+            Err(_) => {} // 无法 stat 的输入稍后会报告错误，不影响列宽
+        }
     }
+    let digits = regular_total.checked_ilog10().map_or(1, |log| log as usize + 1);
+    digits.max(minimum_width)
+}
 
-     #[test]
-    fn test_only_newlines() {
-        let content = "\n\n\n"; // 3 newlines -> 3 lines
-        let cli = Cli { lines: true, words: true, bytes: true, chars: true, files: vec![] };
-        let stats = process_input(mock_reader_from_string(content), &cli, "test_newlines").unwrap();
-        assert_eq!(stats.lines, 3);
-        assert_eq!(stats.words, 0); // No words
-        assert_eq!(stats.bytes, 3);
-        assert_eq!(stats.chars, 3); // Newline is a char
+// io::Error 的 Display 带有 " (os error N)" 后缀，wc 的错误信息中没有这部分
+fn io_error_message(e: &io::Error) -> String {
+    let message = e.to_string();
+    match e.raw_os_error() {
+        Some(code) => message.trim_end_matches(&format!(" (os error {})", code)).to_string(),
+        None => message,
     }
 }
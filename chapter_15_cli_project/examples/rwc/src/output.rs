@@ -0,0 +1,455 @@
+// 统计结果的输出格式。
+//
+// - table: 与 wc 相同的右对齐列，供人阅读 (列宽由调用方按 GNU wc 的规则计算)；
+// - json:  一个 JSON 文档 `{"files": [...], "total": {...}}`，每个文件一个对象；
+// - csv / tsv: 带表头的表格，第一列 `kind` 为 `file` 或 `total`，第二列为文件名。
+//
+// 机器可读格式的字段名 (`file`, `lines`, `words`, `chars`, `graphemes`, `bytes`, `compressed_bytes`,
+// `max_line_length`, `invalid`)
+// 是稳定的，只包含请求了的计数器，顺序与表格中的列顺序相同，并且默认总是包含总计。
+// 列顺序与 GNU wc 相同：行、词、字符、字节、最长行。
+//
+// --code-stats 时每行还包含 `code`, `comment`, `blank` 三列，并在总计之后输出按语言的汇总：
+// 表格格式是一张单独的小表，csv/tsv 中是 `kind` 为 `language` 的行，JSON 中是 `languages` 数组。
+
+use crate::{Cli, TotalMode};
+use rwc::Stats;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")] // 配置文件中的写法与命令行相同
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl Format {
+    // 机器可读格式总是输出总计，表格只在多个文件时输出 (与 wc 一致)
+    pub fn always_prints_total(self) -> bool {
+        self != Format::Table
+    }
+}
+
+// 可输出的计数器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Lines,
+    Words,
+    Chars,
+    Graphemes,
+    Bytes,
+    CompressedBytes,
+    MaxLineLength,
+    Invalid,
+    Code,
+    Comment,
+    Blank,
+}
+
+impl Field {
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Lines => "lines",
+            Field::Words => "words",
+            Field::Chars => "chars",
+            Field::Graphemes => "graphemes",
+            Field::Bytes => "bytes",
+            Field::CompressedBytes => "compressed_bytes",
+            Field::MaxLineLength => "max_line_length",
+            Field::Invalid => "invalid",
+            Field::Code => "code",
+            Field::Comment => "comment",
+            Field::Blank => "blank",
+        }
+    }
+
+    pub fn value(self, stats: &Stats) -> usize {
+        match self {
+            Field::Lines => stats.lines,
+            Field::Words => stats.words,
+            Field::Chars => stats.chars,
+            Field::Graphemes => stats.graphemes,
+            Field::Bytes => stats.bytes,
+            Field::CompressedBytes => stats.compressed_bytes as usize,
+            Field::MaxLineLength => stats.max_line_length,
+            Field::Invalid => stats.invalid,
+            Field::Code => stats.code_lines,
+            Field::Comment => stats.comment_lines,
+            Field::Blank => stats.blank_lines,
+        }
+    }
+}
+
+// 按输出顺序列出 cli 中请求的计数器
+pub fn requested_fields(cli: &Cli) -> Vec<Field> {
+    [
+        (cli.lines, Field::Lines),
+        (cli.words, Field::Words),
+        (cli.chars, Field::Chars), // 只有显式指定 -m 时才打印字符数
+        (cli.graphemes, Field::Graphemes),
+        (cli.bytes, Field::Bytes), // 默认行为包含字节，或显式指定 -c
+        (cli.decompress.is_some(), Field::CompressedBytes), // --decompress 时同时打印解压前的字节数
+        (cli.max_line_length, Field::MaxLineLength), // 只有显式指定 -L 时才打印最长行
+        (cli.invalid, Field::Invalid), // 只有显式指定 --invalid 时才打印无效序列数
+        (cli.code_stats, Field::Code),
+        (cli.code_stats, Field::Comment),
+        (cli.code_stats, Field::Blank),
+    ]
+    .into_iter()
+    .filter_map(|(requested, field)| requested.then_some(field))
+    .collect()
+}
+
+// JSON 中的一组计数，未请求的计数器不会出现在输出中
+#[derive(Serialize, Default)]
+pub struct Counts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chars: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    graphemes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compressed_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_line_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invalid: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blank: Option<usize>,
+}
+
+impl Counts {
+    pub fn new(fields: &[Field], stats: &Stats) -> Self {
+        let mut counts = Counts::default();
+        for &field in fields {
+            let slot = match field {
+                Field::Lines => &mut counts.lines,
+                Field::Words => &mut counts.words,
+                Field::Chars => &mut counts.chars,
+                Field::Graphemes => &mut counts.graphemes,
+                Field::Bytes => &mut counts.bytes,
+                Field::CompressedBytes => &mut counts.compressed_bytes,
+                Field::MaxLineLength => &mut counts.max_line_length,
+                Field::Invalid => &mut counts.invalid,
+                Field::Code => &mut counts.code,
+                Field::Comment => &mut counts.comment,
+                Field::Blank => &mut counts.blank,
+            };
+            *slot = Some(field.value(stats));
+        }
+        counts
+    }
+}
+
+#[derive(Serialize)]
+struct FileRecord {
+    file: String,
+    #[serde(flatten)]
+    counts: Counts,
+}
+
+#[derive(Serialize)]
+struct LanguageRecord {
+    language: &'static str,
+    files: usize,
+    #[serde(flatten)]
+    counts: Counts,
+}
+
+// --total=only 时没有 files，--total=never 时没有 total，只有 --code-stats 时才有 languages
+#[derive(Serialize)]
+struct JsonReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<FileRecord>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<Counts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    languages: Option<Vec<LanguageRecord>>,
+}
+
+// --code-stats 中一种语言的汇总
+pub struct LanguageSummary {
+    pub name: &'static str,
+    pub files: usize,
+    pub stats: Stats, // 该语言所有文件的统计之和
+}
+
+// 按选定的格式逐行输出统计结果
+pub struct Reporter<W: Write> {
+    out: W,
+    format: Format,
+    fields: Vec<Field>,
+    width: usize, // 表格中每列的最小宽度
+    total_mode: TotalMode,
+    header_written: bool,
+    json_files: Vec<FileRecord>, // JSON 需要在最后一次性输出整个文档
+    languages: Option<Vec<LanguageSummary>>,
+}
+
+impl<W: Write> Reporter<W> {
+    pub fn new(out: W, format: Format, fields: Vec<Field>, width: usize, total_mode: TotalMode) -> Self {
+        Reporter { out, format, fields, width, total_mode, header_written: false, json_files: Vec::new(), languages: None }
+    }
+
+    // 设置按语言的汇总，在 finish 时输出在总计之后
+    pub fn languages(&mut self, languages: Vec<LanguageSummary>) {
+        self.languages = Some(languages);
+    }
+
+    // 输出一个文件的统计结果；`name` 为 None 表示没有文件参数、从 stdin 读取
+    pub fn file(&mut self, name: Option<&str>, stats: &Stats) -> io::Result<()> {
+        if self.total_mode == TotalMode::Only {
+            return Ok(()); // 只输出总计
+        }
+        match self.format {
+            Format::Table => self.table_row(name, stats),
+            Format::Json => {
+                self.json_files.push(FileRecord {
+                    file: name.unwrap_or("-").to_string(),
+                    counts: Counts::new(&self.fields, stats),
+                });
+                Ok(())
+            }
+            Format::Csv | Format::Tsv => self.delimited_row("file", name.unwrap_or("-"), stats),
+        }
+    }
+
+    // 按 --total 的设置输出总计，并结束输出 (JSON 文档在这里一次性输出)。
+    // `input_count` 是输入的个数：auto 模式下表格只在多于一个输入时输出总计 (与 wc 一致)，
+    // 机器可读格式总是输出总计。
+    pub fn finish(&mut self, stats: &Stats, input_count: usize) -> io::Result<()> {
+        let print_total = match self.total_mode {
+            TotalMode::Auto => input_count > 1 || self.format.always_prints_total(),
+            TotalMode::Always | TotalMode::Only => true,
+            TotalMode::Never => false,
+        };
+        match self.format {
+            Format::Table if print_total => {
+                // --total=only 时只输出数字，不带 total 标签
+                let label = (self.total_mode != TotalMode::Only).then_some("total");
+                self.table_row(label, stats)?;
+            }
+            Format::Table => {}
+            Format::Json => {
+                let languages = self.languages.take().map(|languages| {
+                    languages
+                        .iter()
+                        .map(|summary| LanguageRecord {
+                            language: summary.name,
+                            files: summary.files,
+                            counts: Counts::new(&self.fields, &summary.stats),
+                        })
+                        .collect()
+                });
+                let report = JsonReport {
+                    files: (self.total_mode != TotalMode::Only).then(|| std::mem::take(&mut self.json_files)),
+                    total: print_total.then(|| Counts::new(&self.fields, stats)),
+                    languages,
+                };
+                serde_json::to_writer(&mut self.out, &report)?;
+                writeln!(self.out)?;
+            }
+            Format::Csv | Format::Tsv if print_total => self.delimited_row("total", "", stats)?,
+            Format::Csv | Format::Tsv => self.write_header()?, // 没有任何行时也输出表头
+        }
+        if let Some(languages) = self.languages.take() {
+            match self.format {
+                Format::Table => self.language_table(&languages)?,
+                Format::Json => {}
+                Format::Csv | Format::Tsv => {
+                    for summary in &languages {
+                        self.delimited_row("language", summary.name, &summary.stats)?;
+                    }
+                }
+            }
+        }
+        self.out.flush()
+    }
+
+    // 表格格式的语言汇总：与上面的计数之间空一行，语言名左对齐，数字右对齐
+    fn language_table(&mut self, languages: &[LanguageSummary]) -> io::Result<()> {
+        const HEADER: [&str; 5] = ["language", "files", "code", "comment", "blank"];
+        let rows: Vec<[String; 5]> = languages
+            .iter()
+            .map(|summary| {
+                [
+                    summary.name.to_string(),
+                    summary.files.to_string(),
+                    summary.stats.code_lines.to_string(),
+                    summary.stats.comment_lines.to_string(),
+                    summary.stats.blank_lines.to_string(),
+                ]
+            })
+            .collect();
+        let widths: Vec<usize> = (0..HEADER.len())
+            .map(|column| rows.iter().map(|row| row[column].len()).chain([HEADER[column].len()]).max().unwrap_or(0))
+            .collect();
+
+        writeln!(self.out)?;
+        let header = HEADER.map(String::from);
+        for row in std::iter::once(&header).chain(&rows) {
+            let mut line = format!("{:<width$}", row[0], width = widths[0]);
+            for (cell, width) in row[1..].iter().zip(&widths[1..]) {
+                line.push_str(&format!("  {:>width$}", cell));
+            }
+            writeln!(self.out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn table_row(&mut self, name: Option<&str>, stats: &Stats) -> io::Result<()> {
+        let width = self.width;
+        let output_parts: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| format!("{:>width$}", field.value(stats))) // 右对齐
+            .collect();
+        match name {
+            Some(name) => writeln!(self.out, "{} {}", output_parts.join(" "), name),
+            None => writeln!(self.out, "{}", output_parts.join(" ")), // 对于 stdin 且无文件名的情况，只打印数字
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        if self.format == Format::Csv { "," } else { "\t" }
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            let header: Vec<&str> = ["kind", "file"]
+                .into_iter()
+                .chain(self.fields.iter().map(|field| field.name()))
+                .collect();
+            writeln!(self.out, "{}", header.join(self.separator()))?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
+    fn delimited_row(&mut self, kind: &str, name: &str, stats: &Stats) -> io::Result<()> {
+        self.write_header()?;
+        let separator = self.separator();
+        let name = match self.format {
+            Format::Csv => escape_csv(name),
+            _ => escape_tsv(name),
+        };
+        let mut record = vec![kind.to_string(), name];
+        record.extend(self.fields.iter().map(|field| field.value(stats).to_string()));
+        writeln!(self.out, "{}", record.join(separator))
+    }
+}
+
+// RFC 4180：包含分隔符、引号或换行的字段用双引号括起来，内部的双引号写两遍
+pub fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// TSV 不允许字段中出现制表符和换行，用反斜杠转义
+pub fn escape_tsv(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: Format, rows: &[(Option<&str>, Stats)], total: Stats) -> String {
+        render_with(format, TotalMode::Auto, rows, total)
+    }
+
+    fn render_with(format: Format, total_mode: TotalMode, rows: &[(Option<&str>, Stats)], total: Stats) -> String {
+        let fields = vec![Field::Lines, Field::Words, Field::Bytes];
+        let mut reporter = Reporter::new(Vec::new(), format, fields, 7, total_mode);
+        for (name, stats) in rows {
+            reporter.file(*name, stats).unwrap();
+        }
+        reporter.finish(&total, rows.len()).unwrap();
+        String::from_utf8(reporter.out).unwrap()
+    }
+
+    fn stats(lines: usize, words: usize, bytes: usize) -> Stats {
+        Stats { lines, words, bytes, ..Stats::default() }
+    }
+
+    #[test]
+    fn test_json_report() {
+        let output = render(Format::Json, &[(Some("a.txt"), stats(1, 3, 17))], stats(1, 3, 17));
+        assert_eq!(
+            output,
+            "{\"files\":[{\"file\":\"a.txt\",\"lines\":1,\"words\":3,\"bytes\":17}],\"total\":{\"lines\":1,\"words\":3,\"bytes\":17}}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_and_tsv_escaping() {
+        let rows = [(Some("a,\"b\".txt"), stats(1, 2, 3)), (Some("tab\there"), stats(4, 5, 6))];
+        let csv = render(Format::Csv, &rows, stats(5, 7, 9));
+        assert_eq!(
+            csv,
+            "kind,file,lines,words,bytes\nfile,\"a,\"\"b\"\".txt\",1,2,3\nfile,tab\there,4,5,6\ntotal,,5,7,9\n"
+        );
+        let tsv = render(Format::Tsv, &rows, stats(5, 7, 9));
+        assert_eq!(
+            tsv,
+            "kind\tfile\tlines\twords\tbytes\nfile\ta,\"b\".txt\t1\t2\t3\nfile\ttab\\there\t4\t5\t6\ntotal\t\t5\t7\t9\n"
+        );
+    }
+
+    #[test]
+    fn test_stdin_row_name() {
+        let table = {
+            let mut reporter = Reporter::new(Vec::new(), Format::Table, vec![Field::Lines], 7, TotalMode::Auto);
+            reporter.file(None, &stats(2, 0, 0)).unwrap();
+            reporter.finish(&stats(2, 0, 0), 1).unwrap();
+            String::from_utf8(reporter.out).unwrap()
+        };
+        assert_eq!(table, "      2\n");
+        let csv = render(Format::Csv, &[(None, stats(2, 4, 23))], stats(2, 4, 23));
+        assert!(csv.contains("\nfile,-,2,4,23\n"));
+    }
+
+    #[test]
+    fn test_total_modes() {
+        let rows = [(Some("a"), stats(1, 3, 17)), (Some("b"), stats(2, 9, 46))];
+        let total = stats(3, 12, 63);
+        assert_eq!(
+            render_with(Format::Table, TotalMode::Only, &rows, total),
+            "      3      12      63\n"
+        );
+        assert_eq!(
+            render_with(Format::Table, TotalMode::Never, &rows, total),
+            "      1       3      17 a\n      2       9      46 b\n"
+        );
+        assert_eq!(
+            render_with(Format::Table, TotalMode::Always, &rows[..1], stats(1, 3, 17)),
+            "      1       3      17 a\n      1       3      17 total\n"
+        );
+        assert_eq!(
+            render_with(Format::Json, TotalMode::Only, &rows, total),
+            "{\"total\":{\"lines\":3,\"words\":12,\"bytes\":63}}\n"
+        );
+        assert_eq!(render_with(Format::Csv, TotalMode::Never, &[], total), "kind,file,lines,words,bytes\n");
+    }
+}
@@ -0,0 +1,113 @@
+// 简单的工作线程池：在多个线程上并行处理输入，但按输入顺序交付结果。
+//
+// 工作线程通过一个共享的原子下标领取任务，结果经 mpsc 通道发回调用线程；
+// 调用线程把提前完成的结果暂存在 BTreeMap 中，只有前面的结果都交付后才交付下一个，
+// 因此输出顺序与顺序执行时完全相同，同时已完成的前缀可以立即输出，不必等待全部完成。
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+// 把 `--jobs` 的值转换为实际线程数：0 表示使用全部可用的 CPU
+pub fn effective_jobs(jobs: usize) -> usize {
+    if jobs == 0 {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        jobs
+    }
+}
+
+// 在最多 `jobs` 个线程上对每个元素调用 `work`，并按 `items` 的顺序把结果交给 `emit`。
+// `emit` 返回错误时停止分发新任务，等待已开始的任务结束后返回该错误。
+pub fn for_each_ordered<T, R, W, E, Error>(items: &[T], jobs: usize, work: W, mut emit: E) -> Result<(), Error>
+where
+    T: Sync,
+    R: Send,
+    W: Fn(&T) -> R + Sync,
+    E: FnMut(usize, R) -> Result<(), Error>,
+{
+    let jobs = jobs.min(items.len());
+    if jobs <= 1 {
+        // 单线程时直接顺序执行，避免创建线程的开销
+        for (index, item) in items.iter().enumerate() {
+            emit(index, work(item))?;
+        }
+        return Ok(());
+    }
+
+    let next_index = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let (next_index, work) = (&next_index, &work);
+            scope.spawn(move || loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else { break };
+                if tx.send((index, work(item))).is_err() {
+                    break; // 接收端已放弃 (emit 出错)
+                }
+            });
+        }
+        drop(tx); // 所有工作线程结束后通道关闭，下面的循环随之结束
+
+        let mut ready = BTreeMap::new();
+        let mut next_to_emit = 0;
+        for (index, result) in rx {
+            ready.insert(index, result);
+            while let Some(result) = ready.remove(&next_to_emit) {
+                if let Err(e) = emit(next_to_emit, result) {
+                    // 让工作线程不再领取新任务
+                    next_index.store(items.len(), Ordering::Relaxed);
+                    return Err(e);
+                }
+                next_to_emit += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_results_are_emitted_in_order() {
+        let items: Vec<u64> = (0..50).collect();
+        let mut emitted = Vec::new();
+        for_each_ordered(
+            &items,
+            8,
+            |&n| {
+                // 让前面的任务更慢，迫使结果乱序完成
+                thread::sleep(Duration::from_millis((50 - n) % 7));
+                n * n
+            },
+            |index, square| {
+                emitted.push((index, square));
+                Ok::<(), ()>(())
+            },
+        )
+        .unwrap();
+        let expected: Vec<(usize, u64)> = items.iter().map(|&n| (n as usize, n * n)).collect();
+        assert_eq!(emitted, expected);
+    }
+
+    #[test]
+    fn test_emit_error_stops_processing() {
+        let items: Vec<usize> = (0..100).collect();
+        let mut emitted = 0;
+        let result = for_each_ordered(&items, 4, |&n| n, |index, _| {
+            if index == 10 {
+                return Err("stop");
+            }
+            emitted += 1;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert_eq!(emitted, 10);
+    }
+}
@@ -0,0 +1,99 @@
+// --record-separator：按正则表达式划分记录，统计记录数代替行数。
+//
+// 记录是分隔符之间的内容 (与 awk 的 RS 相同)：每个分隔符结束一条记录，连续的两个分隔符
+// 之间是一条空记录；与行数不同，最后一个分隔符之后如果还有内容，也算一条记录。
+// 分隔符不能匹配空串。
+//
+// 流式匹配：块末尾的匹配可能随着后续数据变长 (例如 `\n+`)，所以只接受结束位置之后
+// 还有数据的匹配，其余内容留到下一块再匹配。一条记录很长时只保留末尾 `MAX_SEPARATOR_LEN`
+// 字节，因此分隔符本身的长度不能超过这个值；需要任意远的上下文才能确定的模式
+// (例如 `a.*z`) 也只在保留的范围内匹配。
+
+use regex::bytes::Regex;
+
+// 分隔符的最大长度
+const MAX_SEPARATOR_LEN: usize = 4096;
+
+// 待匹配的内容超过这个长度时丢弃前面的部分
+const MAX_PENDING: usize = 1 << 16;
+
+#[derive(Debug, Clone)]
+pub struct RecordCounter {
+    separator: Regex,
+    pending: Vec<u8>,    // 最后一个分隔符之后尚未确定的内容
+    records: usize,      // 已经结束的记录数
+    discarded: bool,     // 当前记录是否有内容已经从 pending 中丢弃
+}
+
+impl RecordCounter {
+    pub fn new(separator: Regex) -> Self {
+        RecordCounter { separator, pending: Vec::new(), records: 0, discarded: false }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let consumed = self.scan(false);
+        self.pending.drain(..consumed);
+        if self.pending.len() > MAX_PENDING {
+            self.pending.drain(..self.pending.len() - MAX_SEPARATOR_LEN);
+            self.discarded = true;
+        }
+    }
+
+    pub fn finish(mut self) -> usize {
+        let consumed = self.scan(true);
+        let trailing = self.discarded || consumed < self.pending.len();
+        self.records + usize::from(trailing)
+    }
+
+    // 统计 pending 中的分隔符，返回最后一个被接受的分隔符的结束位置
+    fn scan(&mut self, at_end: bool) -> usize {
+        let mut consumed = 0;
+        for separator in self.separator.find_iter(&self.pending) {
+            if separator.is_empty() {
+                continue;
+            }
+            if separator.end() == self.pending.len() && !at_end {
+                break;
+            }
+            self.records += 1;
+            self.discarded = false;
+            consumed = separator.end();
+        }
+        consumed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(separator: &str, input: &[u8], chunk_size: usize) -> usize {
+        let mut counter = RecordCounter::new(Regex::new(separator).unwrap());
+        for chunk in input.chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        counter.finish()
+    }
+
+    #[test]
+    fn test_records() {
+        let input = b"first\n\nsecond\nstill second\n\n\nthird";
+        for chunk_size in [1, 2, 5, input.len()] {
+            // 空行分隔的段落：分隔符可以跨块变长
+            assert_eq!(count(r"\n\n+", input, chunk_size), 3, "chunk_size = {}", chunk_size);
+            assert_eq!(count(r"\n", input, chunk_size), 7, "chunk_size = {}", chunk_size);
+        }
+        assert_eq!(count(r"\n", b"a\nb\n", 1), 2); // 分隔符结尾时没有额外的记录
+        assert_eq!(count(r"\n", b"", 1), 0);
+        assert_eq!(count(r"\r\n|\r|\n", b"a\r\nb\rc\n", 1), 3);
+    }
+
+    #[test]
+    fn test_long_records() {
+        let mut input = vec![b'x'; 3 * MAX_PENDING];
+        input.extend_from_slice(b"--\ny");
+        assert_eq!(count(r"--\n", &input, 8192), 2);
+        assert_eq!(count(r"--\n", &input[..3 * MAX_PENDING], 8192), 1);
+    }
+}
@@ -0,0 +1,213 @@
+// --unicode-words 和 --graphemes：按 Unicode 文本分段规则 (UAX #29) 计数。
+//
+// - 单词：UAX #29 单词边界之间、含有字母或数字的片段 (`unicode_words`)。
+//   与按空白分词不同，汉字、假名等每个字都是一个单词，"don't"、"3.14" 各算一个单词，标点不算单词；
+// - 字素 (grapheme)：用户感知的字符，例如 "e\u{301}"、国旗 emoji、"\r\n" 都只算一个。
+//
+// 两种边界在换行符之后总是断开，所以可以逐行分段，只缓存当前这一行。
+// 为了在没有换行符的超长行上也保持内存有界，缓冲区超过 MAX_BUFFERED 时在
+// "空格或制表符后接 ASCII 可见字符" 处提前分段——两种规则在这里也一定是边界。
+// 每次只检查新追加的字节，所以总的时间与输入长度成正比。
+// 没有这样的位置时 (例如不含空格的中文、压缩过的 JSON、二进制数据)，缓冲区超过 MAX_LINE 时强制分段：
+// 优先在单词边界处断开 (也一定是字素边界)，其次在字素边界处断开，这时被断开的单词由下一段接着计数，
+// 不会算成两个；切分点之后至少保留 LOOKAHEAD 字节，使得边界的判断不依赖尚未读到的内容。
+// 只有一个字素长达 MAX_LINE 字节的病态输入才会在字素内部断开，多算一个字素。
+// 无效的 UTF-8 序列不计入任何单词或字素，并且总是断开两侧的片段。
+
+use unicode_segmentation::UnicodeSegmentation;
+
+const MAX_BUFFERED: usize = 64 * 1024;
+const MAX_LINE: usize = 4 * MAX_BUFFERED;
+const LOOKAHEAD: usize = 64;
+
+#[derive(Debug, Default, Clone)]
+pub struct SegmentCounter {
+    line: Vec<u8>,             // 尚未分段的字节
+    scanned: usize,            // `line` 中已经找过 safe_boundary 的长度
+    boundary: Option<usize>,   // `line` 中已找到的最后一个 safe_boundary
+    word_open: bool,           // 上一段在一个单词的中间被强制断开，`line` 开头是这个单词的其余部分
+    pub words: usize,
+    pub graphemes: usize,
+}
+
+impl SegmentCounter {
+    pub fn new() -> Self {
+        SegmentCounter::default()
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        let mut rest = bytes;
+        while let Some(pos) = rest.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&rest[..=pos]);
+            self.flush_all();
+            rest = &rest[pos + 1..];
+        }
+        self.line.extend_from_slice(rest);
+        if self.line.len() > MAX_BUFFERED {
+            self.flush_head();
+        }
+    }
+
+    // 处理剩余的字节，返回 (单词数, 字素数)
+    pub fn finish(mut self) -> (usize, usize) {
+        self.flush_all();
+        (self.words, self.graphemes)
+    }
+
+    fn flush_all(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        self.count(&line);
+        self.line = line;
+        self.line.clear(); // 复用缓冲区
+        self.scanned = 0;
+        self.boundary = None;
+    }
+
+    // 缓冲区过长时统计并丢弃前面可以安全分段的部分
+    fn flush_head(&mut self) {
+        // 跨越上次末尾的一对字节也要检查
+        let start = self.scanned.saturating_sub(1);
+        if let Some(boundary) = safe_boundary(&self.line[start..]) {
+            self.boundary = Some(start + boundary);
+        }
+        self.scanned = self.line.len();
+
+        let (cut, word_open) = match self.boundary {
+            Some(boundary) => (boundary, false),
+            None if self.line.len() > MAX_LINE => forced_boundary(&self.line),
+            None => return,
+        };
+        let head: Vec<u8> = self.line.drain(..cut).collect();
+        self.count(&head);
+        self.word_open = word_open;
+        self.scanned -= cut;
+        self.boundary = None; // 找到的是最后一个，剩下的部分中没有
+    }
+
+    fn count(&mut self, bytes: &[u8]) {
+        for chunk in bytes.utf8_chunks() {
+            let valid = chunk.valid();
+            self.words += valid.unicode_words().count();
+            self.graphemes += valid.graphemes(true).count();
+        }
+        // 被强制断开的单词在上一段中已经计数
+        if std::mem::take(&mut self.word_open) {
+            let continued = bytes.utf8_chunks().next().and_then(|chunk| chunk.valid().unicode_word_indices().next());
+            if continued.is_some_and(|(start, _)| start == 0) {
+                self.words -= 1;
+            }
+        }
+    }
+}
+
+// 最后一个 "空格或制表符后接 ASCII 可见字符" 的位置
+fn safe_boundary(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(2)
+        .rposition(|pair| matches!(pair[0], b' ' | b'\t') && pair[1].is_ascii_graphic())
+        .map(|i| i + 1)
+}
+
+// 强制分段的位置和切分点之前的部分是否以 (被断开的) 单词结尾：不超过 `bytes.len() - LOOKAHEAD` 的
+// 最后一个单词边界，它太靠前 (至少要丢弃一半，才能保证总的时间是线性的) 时是最后一个字素边界，
+// 再没有时是最后一个字符边界。无效序列之后总是边界
+fn forced_boundary(bytes: &[u8]) -> (usize, bool) {
+    let limit = bytes.len() - LOOKAHEAD;
+    let mut word_cut = None;
+    let mut grapheme_cut = None;
+    let mut open_word = false; // grapheme_cut 之前、同一个片段中的部分是否计为单词
+    let mut offset = 0;
+    for chunk in bytes.utf8_chunks() {
+        let valid = chunk.valid();
+        for (start, segment) in valid.split_word_bound_indices() {
+            let start = offset + start;
+            if start > limit {
+                break;
+            }
+            if start > 0 {
+                word_cut = Some(start);
+            }
+            for (index, _) in segment.grapheme_indices(true) {
+                if start + index > limit {
+                    break;
+                }
+                if start + index > 0 {
+                    grapheme_cut = Some(start + index);
+                    open_word = segment[..index].chars().any(char::is_alphanumeric);
+                }
+            }
+        }
+        offset += valid.len() + chunk.invalid().len();
+        if !chunk.invalid().is_empty() && offset <= limit {
+            word_cut = Some(offset);
+        }
+    }
+    match (word_cut, grapheme_cut) {
+        (Some(word), _) if word >= limit / 2 => (word, false),
+        (_, Some(grapheme)) if grapheme >= limit / 2 => (grapheme, open_word),
+        _ => ((1..=limit).rev().find(|&i| !(0x80..0xC0).contains(&bytes[i])).unwrap_or(limit), false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(input: &[u8], chunk_size: usize) -> (usize, usize) {
+        let mut counter = SegmentCounter::new();
+        for chunk in input.chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        counter.finish()
+    }
+
+    #[test]
+    fn test_unicode_words() {
+        // 你、好 各是一个单词；标点不是单词；don't 和 3.14 各是一个单词
+        assert_eq!(count("你好 Rustaceans!".as_bytes(), 1024).0, 3);
+        assert_eq!(count("don't stop, 3.14 — ok\n".as_bytes(), 1024).0, 4);
+        assert_eq!(count("こんにちは世界\n".as_bytes(), 1024).0, 7);
+    }
+
+    #[test]
+    fn test_graphemes() {
+        // e + 组合重音、国旗 (两个区域指示符)、带肤色的 emoji、\r\n 各是一个字素
+        let input = "e\u{301}🇨🇳👍🏽\r\n".as_bytes();
+        assert_eq!(count(input, 1024).1, 4);
+        assert_eq!(count(input, 1), (1, 4)); // 逐字节输入结果相同 (é 是一个单词)
+    }
+
+    #[test]
+    fn test_invalid_bytes_split_segments() {
+        assert_eq!(count(b"ab\xffcd\n", 1024), (2, 5));
+    }
+
+    // 分块输入，检查缓冲区不超过 `max_buffered` 并且结果与一次统计整行相同
+    fn assert_bounded(line: &[u8], max_buffered: usize) {
+        let expected = {
+            let mut counter = SegmentCounter::new();
+            counter.count(line);
+            (counter.words, counter.graphemes)
+        };
+        let mut counter = SegmentCounter::new();
+        for chunk in line.chunks(4096) {
+            counter.update(chunk);
+            assert!(counter.line.len() <= max_buffered + 4096);
+        }
+        assert_eq!(counter.finish(), expected);
+    }
+
+    #[test]
+    fn test_long_line_matches_unbuffered() {
+        assert_bounded("word e\u{301} 漢字 ".repeat(MAX_BUFFERED / 8).as_bytes(), MAX_BUFFERED);
+    }
+
+    #[test]
+    fn test_long_line_without_spaces_is_bounded() {
+        // 没有 "空格后接 ASCII" 的切分点时强制分段：中文、压缩过的 JSON、一个很长的单词
+        assert_bounded("漢字かなe\u{301}🇨🇳".repeat(80_000).as_bytes(), MAX_LINE);
+        assert_bounded(r#"{"key":"value","n":3.14,"list":[1,2,3]},"#.repeat(25_000).as_bytes(), MAX_LINE);
+        assert_bounded("x".repeat(1024 * 1024).as_bytes(), MAX_LINE);
+        assert_bounded(&b"ab\xffc\xe4\xbd".repeat(200_000), MAX_LINE);
+    }
+}
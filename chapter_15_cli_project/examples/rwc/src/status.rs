@@ -0,0 +1,82 @@
+// 退出码约定和出错输入的汇总。
+//
+// - 0：所有输入都统计成功；
+// - 1：有输入无法打开或读取 (其余输入照常统计和输出)，或者无法写出结果；
+// - 2：命令行用法错误 (由 clap 报告，也包括运行时才能发现的错误用法，例如 --follow 跟踪 stdin)。
+//
+// 每个出错的输入在发生时立即报告 `rwc: 名称: 错误`，与 GNU wc 相同；有多个输入时，只要有输入出错
+// (哪怕只有一个)，结束前再汇总列出所有出错的输入，以免它们淹没在很长的输出中。
+// 只有一个输入时错误信息本身就是全部输出，不再重复汇总。
+// --strict 时遇到第一个错误就停止：不再统计后面的输入，也不输出总计，汇总说明在哪个输入处停止。
+
+use crate::{io_error_message, Input};
+use std::io;
+use std::process::ExitCode;
+
+pub const FAILURE: u8 = 1; // 用法错误的退出码 2 由 clap 决定
+
+// 提前结束逐个输入的处理的原因
+#[derive(Debug)]
+pub enum Stop {
+    Strict,        // --strict 时遇到了第一个出错的输入
+    Io(io::Error), // 无法写出结果
+}
+
+impl From<io::Error> for Stop {
+    fn from(e: io::Error) -> Self {
+        Stop::Io(e)
+    }
+}
+
+// 记录出错的输入
+pub struct Failures {
+    strict: bool,
+    failed: Vec<String>,
+}
+
+impl Failures {
+    pub fn new(strict: bool) -> Self {
+        Failures { strict, failed: Vec::new() }
+    }
+
+    // 报告一个输入的错误；--strict 时返回 `Stop::Strict`，调用方应当停止处理
+    pub fn record(&mut self, input: &Input, e: &io::Error) -> Result<(), Stop> {
+        let label = match input {
+            Input::Invalid(message) => {
+                eprintln!("rwc: {}", message); // 信息中已经包含了出错的位置
+                message.clone()
+            }
+            _ => {
+                eprintln!("rwc: {}: {}", input.name(), io_error_message(e));
+                input.name()
+            }
+        };
+        self.failed.push(label);
+        if self.strict { Err(Stop::Strict) } else { Ok(()) }
+    }
+
+    // 处理结束：有输入出错时输出汇总 (只有一个输入时除外)，并返回相应的退出码
+    pub fn exit_code(&self, input_count: usize) -> ExitCode {
+        if self.failed.is_empty() {
+            return ExitCode::SUCCESS;
+        }
+        if input_count == 1 {
+            // 错误已经报告过了
+        } else if self.strict {
+            // 只会有一个出错的输入，后面的输入没有统计
+            eprintln!("rwc: {} 无法统计，已停止 (--strict)", self.failed.join(", "));
+        } else {
+            eprintln!("rwc: {} 个输入中有 {} 个无法统计: {}", input_count, self.failed.len(), self.failed.join(", "));
+        }
+        ExitCode::from(FAILURE)
+    }
+}
+
+// 把 `for_each_ordered` 的结果中的 `Stop` 分开：--strict 停止不是错误，写出失败是
+pub fn stopped(result: Result<(), Stop>) -> io::Result<bool> {
+    match result {
+        Ok(()) => Ok(false),
+        Err(Stop::Strict) => Ok(true),
+        Err(Stop::Io(e)) => Err(e),
+    }
+}
@@ -0,0 +1,272 @@
+// --top N：频率分析模式，输出每个文件和总计中最常见的 N 个记号以及行长分布，代替计数表。
+//
+// 输入的展开、并行和出错处理与计数模式相同；--by 选择记号 (见 rwc::freq)。
+// - table: 每个输入一节，以 `==> 文件名 <==` 开头 (与 head/tail 相同)；
+//   记号按 `uniq -c` 的样式输出，行长分布附带按比例缩放的条形图；
+// - json:  `{"by": ..., "files": [...], "total": {...}}`，每个输入包含 `top`、`distinct` 和 `line_lengths`；
+// - csv / tsv: 表头为 `kind,file,section,value,count`，section 为 `top` 或 `line_length`。
+// 总计的输出规则与计数模式的 --total 相同。
+
+use crate::output::{escape_csv, escape_tsv, Format};
+use crate::status::{self, Failures, Stop};
+use crate::{counter_from_cli, Cli, Input, TotalMode};
+use anyhow::Result;
+use rwc::freq::Bucket;
+use rwc::{parallel, Frequencies, TokenKind};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+// 条形图的最大长度
+const BAR_WIDTH: usize = 40;
+
+pub fn run(cli: &Cli, n: usize, inputs: Vec<Input>, show_names: bool) -> Result<ExitCode> {
+    let counter = counter_from_cli(cli);
+    let mut report = FrequencyReport::new(io::stdout().lock(), cli.format, cli.by, n, cli.total);
+    let mut total = Frequencies::default();
+    let mut failures = Failures::new(cli.strict);
+
+    let result = parallel::for_each_ordered(
+        &inputs,
+        parallel::effective_jobs(cli.jobs),
+        |input| match input {
+            Input::Stdin => counter.frequencies(io::stdin(), cli.by),
+            Input::Path(path) => File::open(path).and_then(|file| counter.frequencies(file, cli.by)),
+            Input::Invalid(message) => Err(io::Error::new(io::ErrorKind::InvalidInput, message.clone())),
+        },
+        |index, outcome| -> Result<(), Stop> {
+            let input = &inputs[index];
+            match outcome {
+                Ok(frequencies) => {
+                    report.file(show_names.then(|| input.name()).as_deref(), &frequencies)?;
+                    total += frequencies;
+                }
+                // 出错的输入没有频率可言，只报告错误
+                Err(e) => failures.record(input, &e)?,
+            }
+            Ok(())
+        },
+    );
+    if status::stopped(result)? {
+        return Ok(failures.exit_code(inputs.len()));
+    }
+
+    report.finish(&total, inputs.len())?;
+    Ok(failures.exit_code(inputs.len()))
+}
+
+#[derive(Serialize)]
+struct TokenRecord {
+    token: String,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct BucketRecord {
+    min: usize,
+    max: usize,
+    lines: usize,
+}
+
+#[derive(Serialize)]
+struct FrequencyRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    top: Vec<TokenRecord>,
+    distinct: usize,
+    line_lengths: Vec<BucketRecord>,
+}
+
+impl FrequencyRecord {
+    fn new(file: Option<&str>, frequencies: &Frequencies, n: usize) -> Self {
+        FrequencyRecord {
+            file: file.map(str::to_string),
+            top: frequencies.top(n).into_iter().map(|(token, count)| TokenRecord { token: token.to_string(), count }).collect(),
+            distinct: frequencies.distinct(),
+            line_lengths: frequencies
+                .histogram()
+                .into_iter()
+                .map(|Bucket { min, max, lines }| BucketRecord { min, max, lines })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport {
+    by: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<FrequencyRecord>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<FrequencyRecord>,
+}
+
+// 按选定的格式逐个输入输出频率分析的结果
+struct FrequencyReport<W: Write> {
+    out: W,
+    format: Format,
+    by: TokenKind,
+    n: usize,
+    total_mode: TotalMode,
+    sections: usize, // 已输出的节数，表格中各节之间空一行
+    json_files: Vec<FrequencyRecord>,
+}
+
+impl<W: Write> FrequencyReport<W> {
+    fn new(out: W, format: Format, by: TokenKind, n: usize, total_mode: TotalMode) -> Self {
+        FrequencyReport { out, format, by, n, total_mode, sections: 0, json_files: Vec::new() }
+    }
+
+    // 输出一个输入的结果；`name` 为 None 表示没有文件参数、从 stdin 读取
+    fn file(&mut self, name: Option<&str>, frequencies: &Frequencies) -> io::Result<()> {
+        if self.total_mode == TotalMode::Only {
+            return Ok(());
+        }
+        match self.format {
+            Format::Table => self.table_section(name, frequencies),
+            Format::Json => {
+                self.json_files.push(FrequencyRecord::new(Some(name.unwrap_or("-")), frequencies, self.n));
+                Ok(())
+            }
+            Format::Csv | Format::Tsv => self.delimited_rows("file", name.unwrap_or("-"), frequencies),
+        }
+    }
+
+    fn finish(&mut self, total: &Frequencies, input_count: usize) -> io::Result<()> {
+        let print_total = match self.total_mode {
+            TotalMode::Auto => input_count > 1 || self.format.always_prints_total(),
+            TotalMode::Always | TotalMode::Only => true,
+            TotalMode::Never => false,
+        };
+        match self.format {
+            Format::Table if print_total => {
+                // --total=only 时只有这一节，不带标题
+                let label = (self.total_mode != TotalMode::Only).then_some("total");
+                self.table_section(label, total)?;
+            }
+            Format::Table => {}
+            Format::Json => {
+                let report = JsonReport {
+                    by: self.by.name(),
+                    files: (self.total_mode != TotalMode::Only).then(|| std::mem::take(&mut self.json_files)),
+                    total: print_total.then(|| FrequencyRecord::new(None, total, self.n)),
+                };
+                serde_json::to_writer(&mut self.out, &report)?;
+                writeln!(self.out)?;
+            }
+            Format::Csv | Format::Tsv if print_total => self.delimited_rows("total", "", total)?,
+            Format::Csv | Format::Tsv => {}
+        }
+        self.out.flush()
+    }
+
+    fn table_section(&mut self, name: Option<&str>, frequencies: &Frequencies) -> io::Result<()> {
+        if self.sections > 0 {
+            writeln!(self.out)?;
+        }
+        self.sections += 1;
+        if let Some(name) = name {
+            writeln!(self.out, "==> {} <==", name)?;
+        }
+        // 与 uniq -c 相同：次数右对齐到至少 7 列
+        let top = frequencies.top(self.n);
+        let width = top.first().map_or(0, |(_, count)| count.to_string().len()).max(7);
+        for (token, count) in top {
+            writeln!(self.out, "{:>width$} {}", count, printable(token))?;
+        }
+
+        let histogram = frequencies.histogram();
+        if histogram.is_empty() {
+            return Ok(());
+        }
+        let labels: Vec<String> = histogram.iter().map(range_label).collect();
+        let most = histogram.iter().map(|bucket| bucket.lines).max().unwrap_or(0);
+        let label_width = labels.iter().map(String::len).chain(["length".len()]).max().unwrap_or(0);
+        let lines_width = most.to_string().len().max("lines".len());
+        writeln!(self.out)?;
+        writeln!(self.out, "{:>label_width$} {:>lines_width$}", "length", "lines")?;
+        for (label, bucket) in labels.iter().zip(&histogram) {
+            // 非空的组至少画一格
+            let bar = (bucket.lines * BAR_WIDTH).div_ceil(most);
+            let line = format!("{:>label_width$} {:>lines_width$} {}", label, bucket.lines, "#".repeat(bar));
+            writeln!(self.out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn delimited_rows(&mut self, kind: &str, name: &str, frequencies: &Frequencies) -> io::Result<()> {
+        let (separator, escape): (&str, fn(&str) -> String) = match self.format {
+            Format::Csv => (",", escape_csv),
+            _ => ("\t", escape_tsv),
+        };
+        if self.sections == 0 {
+            writeln!(self.out, "{}", ["kind", "file", "section", "value", "count"].join(separator))?;
+        }
+        self.sections += 1;
+        let name = escape(name);
+        let top = frequencies.top(self.n).into_iter().map(|(token, count)| ("top", escape(token), count));
+        let histogram = frequencies.histogram();
+        let buckets = histogram.iter().map(|bucket| ("line_length", range_label(bucket), bucket.lines));
+        for (section, value, count) in top.chain(buckets) {
+            writeln!(self.out, "{}", [kind, &name, section, &value, &count.to_string()].join(separator))?;
+        }
+        Ok(())
+    }
+}
+
+// 行长分组的标签："0"、"1"、"2-3"……
+fn range_label(bucket: &Bucket) -> String {
+    if bucket.min == bucket.max { bucket.min.to_string() } else { format!("{}-{}", bucket.min, bucket.max) }
+}
+
+// 表格中的记号：控制字符 (例如 --by chars 时的 '\r') 转义后输出，以免破坏表格
+fn printable(token: &str) -> String {
+    token.chars().flat_map(|c| if c.is_control() { c.escape_default().collect() } else { vec![c] }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rwc::Counter;
+
+    fn frequencies(input: &str) -> Frequencies {
+        Counter::new().frequencies(input.as_bytes(), TokenKind::Words).unwrap()
+    }
+
+    fn render(format: Format, rows: &[(Option<&str>, &str)]) -> String {
+        let mut report = FrequencyReport::new(Vec::new(), format, TokenKind::Words, 2, TotalMode::Auto);
+        let mut total = Frequencies::default();
+        for (name, input) in rows {
+            report.file(*name, &frequencies(input)).unwrap();
+            total += frequencies(input);
+        }
+        report.finish(&total, rows.len()).unwrap();
+        String::from_utf8(report.out).unwrap()
+    }
+
+    #[test]
+    fn test_table_sections() {
+        let output = render(Format::Table, &[(Some("a"), "to be or not to be\n"), (Some("b"), "be\n\nor\n")]);
+        assert_eq!(
+            output,
+            "==> a <==\n      2 be\n      2 to\n\nlength lines\n 16-31     1 ########################################\n\n\
+             ==> b <==\n      1 be\n      1 or\n\nlength lines\n     0     1 ####################\n     1     0\n   2-3     2 ########################################\n\n\
+             ==> total <==\n      3 be\n      2 or\n\nlength lines\n     0     1 ####################\n     1     0\n   2-3     2 ########################################\n   4-7     0\n  8-15     0\n 16-31     1 ####################\n"
+        );
+        // stdin 没有标题，也没有总计
+        assert_eq!(render(Format::Table, &[(None, "x\n")]), "      1 x\n\nlength lines\n     1     1 ########################################\n");
+    }
+
+    #[test]
+    fn test_machine_readable() {
+        let json = render(Format::Json, &[(Some("a"), "b a b\n")]);
+        assert_eq!(
+            json,
+            "{\"by\":\"words\",\"files\":[{\"file\":\"a\",\"top\":[{\"token\":\"b\",\"count\":2},{\"token\":\"a\",\"count\":1}],\"distinct\":2,\"line_lengths\":[{\"min\":4,\"max\":7,\"lines\":1}]}],\
+             \"total\":{\"top\":[{\"token\":\"b\",\"count\":2},{\"token\":\"a\",\"count\":1}],\"distinct\":2,\"line_lengths\":[{\"min\":4,\"max\":7,\"lines\":1}]}}\n"
+        );
+        let csv = render(Format::Csv, &[(Some("a,b"), "x\n")]);
+        assert_eq!(csv, "kind,file,section,value,count\nfile,\"a,b\",top,x,1\nfile,\"a,b\",line_length,1,1\ntotal,,top,x,1\ntotal,,line_length,1,1\n");
+    }
+}
@@ -0,0 +1,163 @@
+// --recursive：把目录参数展开为其中的文件。
+//
+// - 目录按文件名排序遍历，输出顺序是确定的；
+// - `--include` 只统计匹配的文件，`--exclude` 跳过匹配的文件和目录 (整个子树)；
+//   模式同时与相对于目录参数的路径和文件名匹配，因此 `*.rs` 和 `src/*.rs`、`target` 都可以使用；
+// - `--gitignore` 时遵循各级目录中的 .gitignore (不要求位于 git 仓库中)，并跳过 .git 目录；
+// - 命令行上直接给出的文件和 stdin 不经过过滤，原样统计。
+
+use crate::Input;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::path::Path;
+
+// --include / --exclude 的参数解析器，无效的模式在解析参数时就报错
+pub fn parse_glob(pattern: &str) -> Result<Glob, globset::Error> {
+    Glob::new(pattern)
+}
+
+pub struct Filter {
+    include: Option<GlobSet>, // None 表示没有 --include，所有文件都统计
+    exclude: GlobSet,
+    gitignore: bool,
+}
+
+impl Filter {
+    pub fn new(include: &[Glob], exclude: &[Glob], gitignore: bool) -> Result<Filter, globset::Error> {
+        let include = if include.is_empty() { None } else { Some(build_set(include)?) };
+        Ok(Filter { include, exclude: build_set(exclude)?, gitignore })
+    }
+
+    // 展开所有目录参数，其他输入保持不变
+    pub fn expand(&self, inputs: Vec<Input>) -> Vec<Input> {
+        let mut expanded = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            match input {
+                Input::Path(path) if path.is_dir() => self.walk(&path, &mut expanded),
+                input => expanded.push(input),
+            }
+        }
+        expanded
+    }
+
+    // 不遍历目录时的过滤 (例如 git 版本中列出的文件)：`path` 是相对路径，
+    // 它本身或任何一级上层目录匹配 --exclude 时跳过
+    pub fn accepts(&self, path: &Path) -> bool {
+        let root = Path::new("");
+        let excluded = path.ancestors().any(|dir| dir != root && matches(&self.exclude, root, dir));
+        !excluded && self.include.as_ref().is_none_or(|set| matches(set, root, path))
+    }
+
+    fn walk(&self, root: &Path, out: &mut Vec<Input>) {
+        let exclude = self.exclude.clone();
+        let skip_git_dir = self.gitignore;
+        let filter_root = root.to_path_buf();
+        let walker = WalkBuilder::new(root)
+            .standard_filters(false) // 默认统计所有文件，包括隐藏文件
+            .git_ignore(self.gitignore)
+            .git_exclude(self.gitignore)
+            .require_git(false)
+            .parents(self.gitignore) // 也遵循目录参数上层的 .gitignore
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                if entry.depth() == 0 {
+                    return true;
+                }
+                if skip_git_dir && entry.file_name() == ".git" && entry.file_type().is_some_and(|t| t.is_dir()) {
+                    return false;
+                }
+                !matches(&exclude, &filter_root, entry.path())
+            })
+            .build();
+
+        for entry in walker {
+            match entry {
+                Ok(entry) => {
+                    let is_file = match entry.file_type() {
+                        Some(file_type) if file_type.is_symlink() => entry.path().is_file(), // 指向文件的符号链接
+                        Some(file_type) => file_type.is_file(),
+                        None => false,
+                    };
+                    if is_file && self.include.as_ref().is_none_or(|set| matches(set, root, entry.path())) {
+                        out.push(Input::Path(entry.into_path()));
+                    }
+                }
+                // 无法读取的目录等：轮到它时报告错误，继续遍历其余部分
+                Err(e) => out.push(Input::Invalid(e.to_string())),
+            }
+        }
+    }
+}
+
+fn build_set(globs: &[Glob]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(glob.clone());
+    }
+    builder.build()
+}
+
+// 用相对于 `root` 的路径和文件名分别匹配
+fn matches(set: &GlobSet, root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    set.is_match(relative) || path.file_name().is_some_and(|name| set.is_match(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn names(inputs: &[Input], root: &Path) -> Vec<String> {
+        inputs
+            .iter()
+            .map(|input| match input {
+                Input::Path(path) => path.strip_prefix(root).unwrap().to_string_lossy().into_owned(),
+                other => panic!("unexpected input {:?}", other),
+            })
+            .collect()
+    }
+
+    fn tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["b.rs", "a.txt", "src/main.rs", "src/lib.rs", "target/debug/out.rs", ".hidden.rs", "gen/x.rs"] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x\n").unwrap();
+        }
+        fs::write(dir.path().join(".gitignore"), "gen/\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_walk_sorted_with_include_exclude() {
+        let dir = tree();
+        let root = dir.path();
+        let include = [parse_glob("*.rs").unwrap()];
+        let exclude = [parse_glob("target").unwrap()];
+
+        let filter = Filter::new(&include, &exclude, false).unwrap();
+        let inputs = filter.expand(vec![Input::Path(root.to_path_buf())]);
+        assert_eq!(names(&inputs, root), [".hidden.rs", "b.rs", "gen/x.rs", "src/lib.rs", "src/main.rs"]);
+
+        let filter = Filter::new(&include, &exclude, true).unwrap();
+        let inputs = filter.expand(vec![Input::Path(root.to_path_buf())]);
+        assert_eq!(names(&inputs, root), [".hidden.rs", "b.rs", "src/lib.rs", "src/main.rs"]);
+    }
+
+    #[test]
+    fn test_accepts_relative_paths() {
+        let filter = Filter::new(&[parse_glob("*.rs").unwrap()], &[parse_glob("target").unwrap()], false).unwrap();
+        assert!(filter.accepts(Path::new("src/main.rs")));
+        assert!(!filter.accepts(Path::new("target/debug/out.rs")));
+        assert!(!filter.accepts(Path::new("README.md")));
+    }
+
+    #[test]
+    fn test_non_directory_inputs_unchanged() {
+        let dir = tree();
+        let filter = Filter::new(&[parse_glob("*.rs").unwrap()], &[], false).unwrap();
+        let inputs = filter.expand(vec![Input::Stdin, Input::Path(dir.path().join("a.txt"))]);
+        assert!(matches!(inputs.as_slice(), [Input::Stdin, Input::Path(p)] if p.ends_with("a.txt")));
+    }
+}
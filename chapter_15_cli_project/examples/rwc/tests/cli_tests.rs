@@ -1,6 +1,8 @@
+mod common;
+
 use assert_cmd::Command; // 用于测试 CLI 程序
+use common::{rwc, rwc_std};
 use predicates::prelude::*; // 用于断言输出
-use std::fs::File;
 use std::io::Write;
 use tempfile::NamedTempFile; // 用于创建临时文件进行测试
 
@@ -18,7 +20,6 @@ fn test_no_args_reads_from_stdin_placeholder() {
     // assert_cmd 本身可能不直接支持模拟这种管道后立即EOF的场景，
     // 但可以测试带文件参数的情况。
     // 对于 stdin，我们可以简单地检查程序在没有参数时是否不会立即 panic。
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
     // 尝试运行，但不提供 stdin 输入，它应该等待。
     // 我们可以设置一个超时，或者只是检查它是否能启动。
     // 为了简单起见，我们这里只做一个基本检查。
@@ -30,16 +31,18 @@ fn test_no_args_reads_from_stdin_placeholder() {
     // 如果程序设计为无参数时读取stdin，它会阻塞，测试也会阻塞。
     // 我们可以通过 `cmd.write_stdin("some input\n").assert()...` 来模拟输入。
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
+    // "hello world\nfrom stdin\n": 2 行, 4 个单词, 12 + 11 = 23 字节
+    // 与 wc 一致，从 stdin 读取时不打印文件名
     cmd.write_stdin("hello world\nfrom stdin\n")
         .assert()
         .success()
-        .stdout(predicate::str::contains("2         3         26").and(predicate::str::contains("-")));
+        .stdout(predicate::str::is_match(r"^\s*2\s+4\s+23\s*$").unwrap());
 }
 
 #[test]
 fn test_file_does_not_exist() {
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     cmd.arg("non_existent_file.txt")
         .assert()
         .failure() // 预期程序会失败 (通常是退出码非0)
@@ -52,7 +55,7 @@ fn test_single_file_default_options() {
     let temp_file = create_temp_file("hello world\nRust programming\n");
     let filepath_str = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     cmd.arg(filepath_str)
         .assert()
         .success()
@@ -74,7 +77,7 @@ fn test_single_file_lines_option() {
     let temp_file = create_temp_file("line1\nline2\nline3\n");
     let filepath_str = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     cmd.arg("-l").arg(filepath_str)
         .assert()
         .success()
@@ -86,7 +89,7 @@ fn test_single_file_words_option() {
     let temp_file = create_temp_file("one two three four");
     let filepath_str = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     cmd.arg("-w").arg(filepath_str)
         .assert()
         .success()
@@ -99,7 +102,7 @@ fn test_single_file_bytes_option() {
     let temp_file = create_temp_file(content);
     let filepath_str = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     cmd.arg("-c").arg(filepath_str)
         .assert()
         .success()
@@ -108,15 +111,15 @@ fn test_single_file_bytes_option() {
 
 #[test]
 fn test_single_file_chars_option() {
-    let content_utf8 = "你好 Rust"; // 你(3) 好(3)  (1) R(1) u(1) s(1) t(1) = 11 bytes, 6 chars
+    let content_utf8 = "你好 Rust"; // 你(3) 好(3)  (1) R(1) u(1) s(1) t(1) = 11 bytes, 7 chars
     let temp_file = create_temp_file(content_utf8);
     let filepath_str = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     cmd.arg("-m").arg(filepath_str)
         .assert()
         .success()
-        .stdout(predicate::str::is_match(format!(r"^\s*6\s*{}\s*$", regex::escape(filepath_str))).unwrap());
+        .stdout(predicate::str::is_match(format!(r"^\s*7\s*{}\s*$", regex::escape(filepath_str))).unwrap());
 }
 
 #[test]
@@ -129,8 +132,8 @@ fn test_single_file_all_options() {
     let temp_file = create_temp_file(content);
     let filepath_str = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
-    cmd.args(&["-l", "-w", "-c", "-m", filepath_str])
+    let mut cmd = rwc();
+    cmd.args(["-l", "-w", "-c", "-m", filepath_str])
         .assert()
         .success()
         .stdout(predicate::str::is_match(format!(r"^\s*2\s+8\s+46\s+46\s*{}\s*$", regex::escape(filepath_str))).unwrap());
@@ -140,17 +143,17 @@ fn test_single_file_all_options() {
 fn test_multiple_files_default_options() {
     let temp_file1 = create_temp_file("file one content\n"); // 1 line, 3 words, 17 bytes
     let path1_str = temp_file1.path().to_str().unwrap();
-    let temp_file2 = create_temp_file("file two has more words here\nand another line\n"); // 2 lines, 8 words, 43 bytes
+    let temp_file2 = create_temp_file("file two has more words here\nand another line\n"); // 2 lines, 9 words, 46 bytes
     let path2_str = temp_file2.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
-    cmd.args(&[path1_str, path2_str])
+    let mut cmd = rwc();
+    cmd.args([path1_str, path2_str])
         .assert()
         .success()
         .stdout(
-            predicate::str::contains(format!("  1   3  17 {}", path1_str))
-            .and(predicate::str::contains(format!("  2   8  43 {}", path2_str)))
-            .and(predicate::str::contains("  3  11  60 total")) // Total: 3 lines, 11 words, 60 bytes
+            predicate::str::is_match(format!(r"(?m)^\s*1\s+3\s+17 {}$", regex::escape(path1_str))).unwrap()
+            .and(predicate::str::is_match(format!(r"(?m)^\s*2\s+9\s+46 {}$", regex::escape(path2_str))).unwrap())
+            .and(predicate::str::is_match(r"(?m)^\s*3\s+12\s+63 total$").unwrap()) // Total: 3 lines, 12 words, 63 bytes
         );
 }
 
@@ -161,15 +164,15 @@ fn test_multiple_files_specific_options() {
     let temp_file2 = create_temp_file("another line here\n"); // 1 line, 3 words
     let path2_str = temp_file2.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     // Request only lines and words
-    cmd.args(&["-l", "-w", path1_str, path2_str])
+    cmd.args(["-l", "-w", path1_str, path2_str])
         .assert()
         .success()
         .stdout(
-            predicate::str::contains(format!("  1   2 {}", path1_str))
-            .and(predicate::str::contains(format!("  1   3 {}", path2_str)))
-            .and(predicate::str::contains("  2   5 total")) // Total: 2 lines, 5 words
+            predicate::str::is_match(format!(r"(?m)^\s*1\s+2 {}$", regex::escape(path1_str))).unwrap()
+            .and(predicate::str::is_match(format!(r"(?m)^\s*1\s+3 {}$", regex::escape(path2_str))).unwrap())
+            .and(predicate::str::is_match(r"(?m)^\s*2\s+5 total$").unwrap()) // Total: 2 lines, 5 words
         );
 }
 
@@ -178,9 +181,676 @@ fn test_empty_file() {
     let temp_file = create_temp_file("");
     let filepath_str = temp_file.path().to_str().unwrap();
 
-    let mut cmd = Command::cargo_bin("rwc").unwrap();
+    let mut cmd = rwc();
     cmd.arg(filepath_str) // Default options
         .assert()
         .success()
         .stdout(predicate::str::is_match(format!(r"^\s*0\s+0\s+0\s*{}\s*$", regex::escape(filepath_str))).unwrap());
 }
+
+#[test]
+fn test_invalid_utf8_file() {
+    // 包含无效 UTF-8 的二进制内容：不应报错，字节/行/词按原始字节统计
+    let mut temp_file = NamedTempFile::new().expect("无法创建临时文件");
+    temp_file.write_all(b"caf\xe9\n\xff\xfe binary\n").expect("无法写入临时文件");
+    let filepath_str = temp_file.path().to_str().unwrap();
+
+    // Lines: 2, Words: caf\xe9, \xff\xfe binary -> 2 (无效字节不开始单词), Bytes: 5 + 10 = 15
+    // Chars: c a f \n space b i n a r y \n -> 12, Invalid: \xe9, \xff, \xfe -> 3
+    // 列顺序与 wc 相同：行、词、字符、字节，最后是无效序列数
+    let mut cmd = rwc();
+    cmd.args(["-l", "-w", "-c", "-m", "--invalid", filepath_str])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(format!(r"^\s*2\s+2\s+12\s+15\s+3\s*{}\s*$", regex::escape(filepath_str))).unwrap());
+}
+
+#[test]
+fn test_parallel_jobs_matches_sequential() {
+    // 大小不一的文件，让并行时各文件以不同顺序完成
+    let temp_files: Vec<NamedTempFile> = (0..40)
+        .map(|i| create_temp_file(&"word ".repeat((i * 37) % 500 + 1).replace("word word", "word\nword")))
+        .collect();
+    let paths: Vec<&str> = temp_files.iter().map(|f| f.path().to_str().unwrap()).collect();
+
+    let sequential = rwc().args(&paths).output().unwrap();
+    assert!(sequential.status.success());
+
+    for jobs in ["2", "8", "0"] {
+        let parallel = rwc()
+            .args(["--jobs", jobs])
+            .args(&paths)
+            .output()
+            .unwrap();
+        assert!(parallel.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&parallel.stdout),
+            String::from_utf8_lossy(&sequential.stdout),
+            "--jobs {} 的输出与顺序处理不一致",
+            jobs
+        );
+    }
+}
+
+#[test]
+fn test_parallel_jobs_reports_missing_file() {
+    let temp_file1 = create_temp_file("one\n");
+    let temp_file2 = create_temp_file("two words\n");
+    let path1_str = temp_file1.path().to_str().unwrap();
+    let path2_str = temp_file2.path().to_str().unwrap();
+
+    let mut cmd = rwc();
+    cmd.args(["-j", "4", path1_str, "non_existent_file.txt", path2_str])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("rwc: non_existent_file.txt:"))
+        .stdout(predicate::str::is_match(r"(?m)^\s*2\s+3\s+14 total$").unwrap());
+}
+
+#[test]
+fn test_parallel_chunked_single_large_file() {
+    // 足够大 (> 2 MiB) 才会触发分块并行统计；内容包含多字节字符，切分点很可能落在字符中间
+    let line = "并行 chunked counting 测试 😊 with\tmixed\u{3000}whitespace\n";
+    let content = line.repeat(3 * 1024 * 1024 / line.len() + 1) + "tail without newline";
+    let temp_file = create_temp_file(&content);
+    let filepath_str = temp_file.path().to_str().unwrap();
+
+    let sequential = rwc()
+        .args(["-l", "-w", "-c", "-m", filepath_str])
+        .output()
+        .unwrap();
+    let parallel = rwc()
+        .args(["-l", "-w", "-c", "-m", "--jobs", "4", filepath_str])
+        .output()
+        .unwrap();
+    assert!(sequential.status.success() && parallel.status.success());
+    assert_eq!(String::from_utf8_lossy(&parallel.stdout), String::from_utf8_lossy(&sequential.stdout));
+}
+
+#[test]
+fn test_format_json() {
+    let temp_file1 = create_temp_file("file one content\n"); // 1 line, 3 words, 17 bytes
+    let temp_file2 = create_temp_file("two\nlines\n"); // 2 lines, 2 words, 10 bytes
+    let path1_str = temp_file1.path().to_str().unwrap();
+    let path2_str = temp_file2.path().to_str().unwrap();
+
+    let output = rwc()
+        .args(["--format", "json", "-l", "-w", "-c", path1_str, path2_str])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("输出应为合法 JSON");
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "files": [
+                { "file": path1_str, "lines": 1, "words": 3, "bytes": 17 },
+                { "file": path2_str, "lines": 2, "words": 2, "bytes": 10 },
+            ],
+            "total": { "lines": 3, "words": 5, "bytes": 27 },
+        })
+    );
+}
+
+#[test]
+fn test_format_csv_and_tsv() {
+    let temp_file = create_temp_file("hello world\n");
+    let filepath_str = temp_file.path().to_str().unwrap();
+
+    let mut cmd = rwc();
+    cmd.args(["--format", "csv", "-m", filepath_str])
+        .assert()
+        .success()
+        .stdout(format!("kind,file,chars\nfile,{},12\ntotal,,12\n", filepath_str));
+
+    let mut cmd = rwc();
+    cmd.args(["--format", "tsv"])
+        .write_stdin("hello world\n")
+        .assert()
+        .success()
+        .stdout("kind\tfile\tlines\twords\tbytes\nfile\t-\t1\t2\t12\ntotal\t\t1\t2\t12\n");
+}
+
+// 以下测试的期望输出都来自 GNU coreutils 9.1 的 wc (LC_ALL=C.UTF-8)，
+// 用于保证 rwc 可以直接替代 wc：列宽、列顺序、错误信息和退出码都应相同。
+fn gnu_fixtures() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    let files: [(&str, &[u8]); 6] = [
+        ("l3", b"one\ntwo\nthree\n"),
+        ("a", b"Hello Rustaceans!\n"),
+        ("b", b"one two three\nfour five six seven eight nine\n"),
+        ("t", "a\tb\n你好\n".as_bytes()),
+        ("nonl", b"a\nb"),
+        ("list", b"l3\0a\0"),
+    ];
+    for (name, content) in files {
+        std::fs::write(dir.path().join(name), content).expect("无法写入临时文件");
+    }
+    std::fs::create_dir(dir.path().join("dd")).expect("无法创建临时目录");
+    dir
+}
+
+fn rwc_in(dir: &tempfile::TempDir) -> Command {
+    let mut cmd = rwc();
+    cmd.current_dir(dir.path());
+    cmd
+}
+
+#[test]
+fn test_gnu_column_width() {
+    let dir = gnu_fixtures();
+    // 单个文件只打印一项计数时不填充
+    rwc_in(&dir).args(["-l", "l3"]).assert().success().stdout("3 l3\n");
+    // 普通文件：宽度为文件大小之和的位数
+    rwc_in(&dir).arg("l3").assert().success().stdout(" 3  3 14 l3\n");
+    rwc_in(&dir).args(["a", "b"]).assert().success().stdout(" 1  2 18 a\n 2  9 45 b\n 3 11 63 total\n");
+    rwc_in(&dir).args(["-c", "l3", "a"]).assert().success().stdout("14 l3\n18 a\n32 total\n");
+    // 最后一行没有换行符时不计入行数
+    rwc_in(&dir).arg("nonl").assert().success().stdout("1 2 3 nonl\n");
+    // stdin 为管道时无法预先知道大小，宽度至少为 7
+    rwc_in(&dir).write_stdin("one\ntwo\nthree\n").assert().success().stdout("      3       3      14\n");
+    rwc_in(&dir).arg("-l").write_stdin("one\ntwo\nthree\n").assert().success().stdout("3\n");
+    rwc_in(&dir).args(["-", "l3"]).write_stdin("x y\n").assert().success()
+        .stdout("      1       2       4 -\n      3       3      14 l3\n      4       5      18 total\n");
+}
+
+#[test]
+fn test_gnu_max_line_length() {
+    let dir = gnu_fixtures();
+    // 制表符跳到下一个 8 的倍数列，CJK 字符宽度为 2
+    rwc_in(&dir).args(["-L", "t"]).assert().success().stdout("9 t\n");
+    rwc_in(&dir).args(["-lwmcL", "t"]).assert().success().stdout(" 2  3  7 11  9 t\n");
+}
+
+#[test]
+fn test_gnu_errors() {
+    // 错误信息与 GNU wc 相同，之后是 rwc 自己的出错输入汇总
+    let dir = gnu_fixtures();
+    rwc_in(&dir).args(["nonexist", "l3"]).assert().code(1)
+        .stdout(" 3  3 14 l3\n 3  3 14 total\n")
+        .stderr("rwc: nonexist: No such file or directory\nrwc: 2 个输入中有 1 个无法统计: nonexist\n");
+    // 目录：报告错误，但仍输出一行 0 计数
+    rwc_in(&dir).args(["dd", "l3"]).assert().code(1)
+        .stdout("      0       0       0 dd\n      3       3      14 l3\n      3       3      14 total\n")
+        .stderr("rwc: dd: Is a directory\nrwc: 2 个输入中有 1 个无法统计: dd\n");
+}
+
+#[test]
+fn test_gnu_files0_from() {
+    let dir = gnu_fixtures();
+    rwc_in(&dir).arg("--files0-from=list").assert().success()
+        .stdout(" 3  3 14 l3\n 1  2 18 a\n 4  5 32 total\n");
+    // 从管道读取文件名时不对齐列宽；stdin 中不允许出现 "-"
+    rwc_in(&dir).arg("--files0-from=-").write_stdin("l3\0a\0-\0").assert().code(1)
+        .stdout("3 3 14 l3\n1 2 18 a\n4 5 32 total\n")
+        .stderr(predicate::str::starts_with("rwc: when reading file names from stdin, no file name of '-' allowed\n"));
+    std::fs::write(dir.path().join("z0"), b"l3\0\0a\0").unwrap();
+    rwc_in(&dir).arg("--files0-from=z0").assert().code(1)
+        .stdout(" 3  3 14 l3\n 1  2 18 a\n 4  5 32 total\n")
+        .stderr(predicate::str::starts_with("rwc: z0:2: invalid zero-length file name\n"));
+    rwc_in(&dir).arg("--files0-from=nope").assert().code(1)
+        .stdout("")
+        .stderr("rwc: cannot open 'nope' for reading: No such file or directory\n");
+    // 文件参数不能与 --files0-from 同时使用
+    rwc_in(&dir).args(["--files0-from=list", "l3"]).assert().failure();
+}
+
+#[test]
+fn test_total_option() {
+    let dir = gnu_fixtures();
+    rwc_in(&dir).args(["--total=only", "a", "b"]).assert().success().stdout(" 3 11 63\n");
+    rwc_in(&dir).args(["--total=never", "a", "b"]).assert().success().stdout(" 1  2 18 a\n 2  9 45 b\n");
+    rwc_in(&dir).args(["--total=always", "a"]).assert().success().stdout(" 1  2 18 a\n 1  2 18 total\n");
+    rwc_in(&dir).args(["--total=never", "--format=csv", "-l", "a"]).assert().success()
+        .stdout("kind,file,lines\nfile,a,1\n");
+}
+
+#[test]
+fn test_recursive_with_include_exclude() {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    for (file, content) in [
+        ("proj/src/main.rs", "fn main() {}\n"),
+        ("proj/src/lib.rs", "pub fn a() {}\npub fn b() {}\n"),
+        ("proj/README.md", "# proj\n"),
+        ("proj/target/gen.rs", "// generated\n"),
+        ("proj/vendor/dep.rs", "x\n"),
+        ("proj/.gitignore", "vendor/\n"),
+    ] {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    // 不递归时与 wc 相同：目录是错误
+    rwc_in(&dir).arg("proj").assert().code(1).stderr("rwc: proj: Is a directory\n");
+
+    rwc_in(&dir)
+        .args(["-r", "-l", "--include", "*.rs", "--exclude", "target", "--gitignore", "proj"])
+        .assert()
+        .success()
+        .stdout(" 2 proj/src/lib.rs\n 1 proj/src/main.rs\n 3 total\n");
+
+    rwc_in(&dir)
+        .args(["-r", "--format=csv", "-l", "--exclude", "*.rs", "proj"])
+        .assert()
+        .success()
+        .stdout("kind,file,lines\nfile,proj/.gitignore,1\nfile,proj/README.md,1\ntotal,,2\n");
+
+    // --include 等过滤选项只能与 --recursive 一起使用
+    rwc_in(&dir).args(["--include", "*.rs", "proj"]).assert().failure();
+}
+
+#[test]
+fn test_code_stats() {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    for (file, content) in [
+        ("main.rs", "/* 块注释\n * 第二行 */\nfn main() {\n    // 注释\n\n    println!(\"// 字符串\");\n}\n"),
+        ("lib.rs", "pub fn f() {}\n"),
+        ("tool.py", "# 注释\nx = '''\n# 字符串\n'''\n"),
+        ("notes.txt", "不属于任何语言\n"),
+    ] {
+        std::fs::write(dir.path().join(file), content).unwrap();
+    }
+
+    rwc_in(&dir)
+        .args(["--code-stats", "-l", "main.rs", "lib.rs", "tool.py", "notes.txt"])
+        .assert()
+        .success()
+        .stdout(
+            "  7   3   3   1 main.rs\n  1   1   0   0 lib.rs\n  4   3   1   0 tool.py\n  1   0   0   0 notes.txt\n 13   7   4   1 total\n\
+             \n\
+             language  files  code  comment  blank\n\
+             Python        1     3        1      0\n\
+             Rust          2     4        3      1\n",
+        );
+
+    rwc_in(&dir)
+        .args(["--code-stats", "-l", "--format=tsv", "main.rs", "tool.py"])
+        .assert()
+        .success()
+        .stdout("kind\tfile\tlines\tcode\tcomment\tblank\nfile\tmain.rs\t7\t3\t3\t1\nfile\ttool.py\t4\t3\t1\t0\n\
+                 total\t\t11\t6\t4\t1\nlanguage\tPython\t4\t3\t1\t0\nlanguage\tRust\t7\t3\t3\t1\n");
+}
+
+#[test]
+fn test_unicode_words_and_graphemes() {
+    let temp_file = create_temp_file("你好 Rustaceans\nnaïve 🇯🇵 ok\n");
+    let filepath_str = temp_file.path().to_str().unwrap();
+    // 按 UAX #29：你 好 Rustaceans naïve ok -> 5 个单词；字符 26 个，国旗是两个字符但只是一个字素
+    let mut cmd = rwc();
+    cmd.args(["-w", "-m", "--graphemes", "--unicode-words", filepath_str])
+        .assert()
+        .success()
+        .stdout(format!(" 5 26 25 {}\n", filepath_str));
+    let mut cmd = rwc();
+    cmd.args(["--graphemes", "--format=json", filepath_str])
+        .assert()
+        .success()
+        .stdout(format!("{{\"files\":[{{\"file\":{:?},\"graphemes\":25}}],\"total\":{{\"graphemes\":25}}}}\n", filepath_str));
+}
+
+#[test]
+fn test_follow_appends_truncation_and_rotation() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    let log = dir.path().join("app.log");
+    std::fs::write(&log, "a b\n").unwrap();
+
+    let mut child = rwc_std()
+        .args(["--follow", "--interval", "0.02", "-l", "-w"])
+        .arg(&log)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("无法启动 rwc");
+    let (sender, frames) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    std::thread::spawn(move || stdout.lines().map_while(Result::ok).try_for_each(|line| sender.send(line)));
+
+    // 等待 `expected` 出现。轮询可能恰好落在两次写入之间，多输出一次中间状态，所以跳过其他的输出
+    let path = log.display().to_string();
+    let mut seen = Vec::new();
+    let mut expect_frame = |expected: String| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match frames.recv_timeout(remaining) {
+                Ok(line) if line == expected => return,
+                Ok(line) => seen.push(line),
+                Err(_) => panic!("没有等到 {:?}，之前的输出: {:?}", expected, seen),
+            }
+        }
+    };
+
+    // 只有一个文件，不输出总计；每当计数变化就输出一次 (列宽随文件大小变化)
+    expect_frame(format!("1 2 {path}"));
+    std::fs::OpenOptions::new().append(true).open(&log).unwrap().write_all(b"c d e\n").unwrap(); // 追加
+    expect_frame(format!(" 2  5 {path}"));
+    std::fs::write(&log, "x\n").unwrap(); // 截断后重写
+    expect_frame(format!("1 1 {path}"));
+    std::fs::rename(&log, dir.path().join("app.log.1")).unwrap(); // 轮转：新文件写好之后再放到原来的位置
+    std::fs::write(dir.path().join("app.log.new"), "new file here\nand more\n").unwrap();
+    std::fs::rename(dir.path().join("app.log.new"), &log).unwrap();
+    expect_frame(format!(" 2  5 {path}"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_decompress() {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    let text = "第一行 line one\nsecond line\n"; // 2 行 5 词 31 字节
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(text.as_bytes()).unwrap();
+    let gzipped = encoder.finish().unwrap();
+    std::fs::write(dir.path().join("log.gz"), &gzipped).unwrap();
+    std::fs::write(dir.path().join("plain.txt"), text).unwrap();
+
+    // 默认统计压缩后的字节
+    rwc_in(&dir).args(["-c", "log.gz"]).assert().success().stdout(format!("{} log.gz\n", gzipped.len()));
+
+    // 自动识别：压缩的文件解压后统计，未压缩的文件按原样统计
+    rwc_in(&dir)
+        .args(["--decompress", "--format=csv", "log.gz", "plain.txt"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "kind,file,lines,words,bytes,compressed_bytes\nfile,log.gz,2,5,31,{}\nfile,plain.txt,2,5,31,31\ntotal,,4,10,62,{}\n",
+            gzipped.len(),
+            gzipped.len() + 31
+        ));
+
+    // 从 stdin 读取压缩数据
+    rwc_in(&dir).args(["--decompress=gzip", "-l"]).write_stdin(gzipped.clone()).assert().success()
+        .stdout(format!("      2 {:>7}\n", gzipped.len()));
+
+    // 指定的格式与内容不符：报告解码错误
+    rwc_in(&dir).args(["--decompress=zstd", "plain.txt"]).assert().code(1)
+        .stderr(predicate::str::starts_with("rwc: plain.txt: "));
+}
+
+#[test]
+fn test_decompress_code_stats() {
+    // 解压时 main.rs.gz 按 Rust 统计，也出现在按语言的汇总中
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(b"// \xe6\xb3\xa8\xe9\x87\x8a\nfn main() {}\n\n").unwrap();
+    std::fs::write(dir.path().join("main.rs.gz"), encoder.finish().unwrap()).unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "pub fn f() {}\n").unwrap();
+
+    rwc_in(&dir)
+        .args(["--decompress", "--code-stats", "-l", "main.rs.gz", "lib.rs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "\n\
+             language  files  code  comment  blank\n\
+             Rust          2     2        1      1\n",
+        ));
+}
+
+#[test]
+fn test_top_frequencies() {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    std::fs::write(dir.path().join("a.txt"), "to be or not to be\n").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "be quick\n").unwrap();
+
+    rwc_in(&dir)
+        .args(["--top", "2", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout(
+            "==> a.txt <==\n      2 be\n      2 to\n\nlength lines\n 16-31     1 ########################################\n\n\
+             ==> b.txt <==\n      1 be\n      1 quick\n\nlength lines\n  8-15     1 ########################################\n\n\
+             ==> total <==\n      3 be\n      2 to\n\nlength lines\n  8-15     1 ########################################\n 16-31     1 ########################################\n",
+        );
+
+    // 相邻单词对；stdin 没有标题和总计；--total=only 只输出总计
+    rwc_in(&dir).args(["--top=1", "--by=bigrams"]).write_stdin("a b a b\n").assert().success()
+        .stdout("      2 a b\n\nlength lines\n   4-7     1 ########################################\n");
+    rwc_in(&dir)
+        .args(["--top=1", "--by=chars", "--total=only", "--format=csv", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout("kind,file,section,value,count\ntotal,,top,o,4\ntotal,,line_length,8-15,1\ntotal,,line_length,16-31,1\n");
+
+    // 出错的输入只报告错误，其余输入照常输出
+    rwc_in(&dir).args(["--top=1", "missing", "b.txt"]).assert().code(1)
+        .stdout(predicate::str::contains("==> b.txt <==\n      1 be\n"))
+        .stderr("rwc: missing: No such file or directory\nrwc: 2 个输入中有 1 个无法统计: missing\n");
+    // --by 需要 --top
+    rwc_in(&dir).args(["--by=chars", "a.txt"]).assert().failure();
+}
+
+#[test]
+fn test_exit_codes_and_failure_summary() {
+    let dir = gnu_fixtures();
+
+    // 0：全部成功
+    rwc_in(&dir).args(["l3", "a"]).assert().code(0).stderr("");
+
+    // 1：有输入出错，其余输入照常输出；有多个输入时在最后汇总出错的输入
+    rwc_in(&dir)
+        .args(["nonexist", "a", "dd"])
+        .assert()
+        .code(1)
+        .stdout("      1       2      18 a\n      0       0       0 dd\n      1       2      18 total\n")
+        .stderr(
+            "rwc: nonexist: No such file or directory\nrwc: dd: Is a directory\n\
+             rwc: 3 个输入中有 2 个无法统计: nonexist, dd\n",
+        );
+    // 只有一个输入出错时同样汇总
+    rwc_in(&dir)
+        .args(["a", "nonexist", "l3"])
+        .assert()
+        .code(1)
+        .stderr("rwc: nonexist: No such file or directory\nrwc: 3 个输入中有 1 个无法统计: nonexist\n");
+    // 只有一个输入时，错误信息本身就是全部输出
+    rwc_in(&dir).arg("nonexist").assert().code(1).stderr("rwc: nonexist: No such file or directory\n");
+
+    // 2：用法错误
+    rwc_in(&dir).arg("--no-such-option").assert().code(2);
+    rwc_in(&dir).args(["--follow", "-"]).assert().code(2).stderr(predicate::str::contains("--follow 不能跟踪 stdin"));
+}
+
+#[test]
+fn test_strict_stops_at_first_error() {
+    let dir = gnu_fixtures();
+
+    // 出错之前的输入照常输出，之后的输入不再统计，也没有总计
+    rwc_in(&dir)
+        .args(["--strict", "a", "nonexist", "b", "dd"])
+        .assert()
+        .code(1)
+        .stdout("      1       2      18 a\n")
+        .stderr("rwc: nonexist: No such file or directory\nrwc: nonexist 无法统计，已停止 (--strict)\n");
+    // 读取失败时也不再输出该输入的一行 0
+    rwc_in(&dir).args(["--strict", "-j", "4", "dd", "a"]).assert().code(1).stdout("")
+        .stderr("rwc: dd: Is a directory\nrwc: dd 无法统计，已停止 (--strict)\n");
+    rwc_in(&dir).args(["--strict", "--format=json", "a", "nonexist"]).assert().code(1).stdout("");
+    rwc_in(&dir).args(["--strict", "a", "b"]).assert().code(0).stdout(predicate::str::ends_with(" total\n"));
+}
+
+#[test]
+fn test_diff_directories_and_lists() {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    let write = |path: &str, content: &str| {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write("old/same.txt", "unchanged\n");
+    write("old/src/lib.rs", "fn a() {}\n");
+    write("old/gone.txt", "bye bye\n");
+    write("new/same.txt", "unchanged\n");
+    write("new/src/lib.rs", "fn a() {}\nfn b() {}\n");
+    write("new/added.md", "# title\n");
+
+    rwc_in(&dir)
+        .args(["diff", "old", "new"])
+        .assert()
+        .success()
+        .stdout(" +1  +2  +8 A added.md\n -1  -2  -8 D gone.txt\n +1  +3 +10 M src/lib.rs\n +1  +3 +10   total\n");
+    rwc_in(&dir)
+        .args(["diff", "--include=*.rs", "--format=csv", "old", "new"])
+        .assert()
+        .success()
+        .stdout(
+            "kind,file,status,lines,words,bytes,old_lines,old_words,old_bytes,new_lines,new_words,new_bytes\n\
+             file,src/lib.rs,modified,1,3,10,1,3,10,2,6,20\ntotal,,modified,1,3,10,1,3,10,2,6,20\n",
+        );
+
+    // 文件列表按列表中的写法配对，--strip 1 去掉开头的 old/ 和 new/，与比较两个目录的结果相同
+    write("a.list", "old/same.txt\nold/src/lib.rs\nold/gone.txt\n");
+    write("b.list", "new/same.txt\nnew/src/lib.rs\nnew/added.md\n");
+    rwc_in(&dir)
+        .args(["diff", "--all", "--strip", "1", "a.list", "b.list"])
+        .assert()
+        .success()
+        .stdout(" +1  +2  +8 A added.md\n -1  -2  -8 D gone.txt\n  0   0   0 = same.txt\n +1  +3 +10 M src/lib.rs\n +1  +3 +10   total\n");
+    rwc_in(&dir)
+        .args(["diff", "a.list", "b.list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("A new/src/lib.rs\n").and(predicate::str::contains("D old/src/lib.rs\n")));
+    // 两侧去掉的层数相同，即使一侧的文件都在同一个子目录中
+    write("one.list", "old/src/lib.rs\n");
+    write("two.list", "new/src/lib.rs\nnew/added.md\n");
+    rwc_in(&dir)
+        .args(["diff", "--strip", "1", "one.list", "two.list"])
+        .assert()
+        .success()
+        .stdout(" +1  +2  +8 A added.md\n +1  +3 +10 M src/lib.rs\n +2  +5 +18   total\n");
+
+    // 无法统计的文件报告错误，退出码为 1
+    write("c.list", "old/same.txt\nold/missing.txt\n");
+    write("d.list", "new/same.txt\n");
+    rwc_in(&dir)
+        .args(["diff", "--all", "--strip", "1", "c.list", "d.list"])
+        .assert()
+        .code(1)
+        .stdout("0 0 0 = same.txt\n0 0 0   total\n")
+        .stderr("rwc: old/missing.txt: No such file or directory\nrwc: 3 个输入中有 1 个无法统计: old/missing.txt\n");
+    rwc_in(&dir).args(["diff", "nope", "new"]).assert().code(1)
+        .stderr("rwc: cannot open 'nope' for reading: No such file or directory\n");
+}
+
+#[test]
+fn test_diff_git_revisions() {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=rwc", "-c", "user.email=rwc@example.com", "-c", "commit.gpgsign=false"])
+            .args(args)
+            .current_dir(dir.path())
+            .stdout(std::process::Stdio::null())
+            .status()
+            .expect("无法运行 git");
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    std::fs::write(dir.path().join("a.txt"), "one two\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "first"]);
+    std::fs::write(dir.path().join("a.txt"), "one two\nthree\n").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "x\n").unwrap();
+    std::fs::create_dir(dir.path().join("docs")).unwrap();
+    std::fs::write(dir.path().join("docs/c.md"), "# c\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "second"]);
+    std::fs::write(dir.path().join("a.txt"), "未提交的修改不影响比较\n").unwrap();
+
+    rwc_in(&dir)
+        .args(["diff", "--git", "HEAD~1", "HEAD"])
+        .assert()
+        .success()
+        .stdout(" +1  +1  +6 M a.txt\n +1  +1  +2 A b.txt\n +1  +2  +4 A docs/c.md\n +3  +4 +12   total\n");
+    // 在子目录中运行时同样比较整个版本，路径相对于仓库根目录
+    rwc()
+        .current_dir(dir.path().join("docs"))
+        .args(["diff", "--git", "HEAD~1", "HEAD"])
+        .assert()
+        .success()
+        .stdout(" +1  +1  +6 M a.txt\n +1  +1  +2 A b.txt\n +1  +2  +4 A docs/c.md\n +3  +4 +12   total\n");
+    rwc_in(&dir).args(["diff", "--git", "HEAD", "no-such-rev"]).assert().code(1);
+}
+
+#[test]
+fn test_config_file_and_environment() {
+    let dir = gnu_fixtures();
+    std::fs::write(dir.path().join("config.toml"), "metrics = [\"lines\"]\nformat = \"csv\"\nwidth = 4\n").unwrap();
+    let configured = || {
+        let mut cmd = rwc_in(&dir);
+        cmd.env("RWC_CONFIG", dir.path().join("config.toml"));
+        cmd
+    };
+
+    configured().arg("l3").assert().success().stdout("kind,file,lines\nfile,l3,3\ntotal,,3\n");
+    // 环境变量优先于配置文件，命令行参数优先于两者
+    configured().env("RWC_FORMAT", "table").arg("l3").assert().success().stdout("   3 l3\n");
+    configured().env("RWC_FORMAT", "table").args(["-w", "--width", "auto", "l3"]).assert().success().stdout("3 l3\n");
+    // 配置的格式同样适用于 diff 子命令
+    std::fs::create_dir_all(dir.path().join("old")).unwrap();
+    std::fs::create_dir_all(dir.path().join("new")).unwrap();
+    std::fs::write(dir.path().join("new/x"), "x\n").unwrap();
+    configured().args(["diff", "old", "new"]).assert().success()
+        .stdout(predicate::str::starts_with("kind,file,status,"));
+    configured()
+        .env("RWC_WIDTH", "auto")
+        .args(["--print-config", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r#"^metrics = \["lines"\] # 配置文件 .*config.toml\nformat = "json" # 命令行\nwidth = "auto" # 环境变量 RWC_WIDTH\n$"#).unwrap());
+
+    // 配置有误属于用法错误
+    configured().env("RWC_METRICS", "lines,pages").arg("l3").assert().code(2)
+        .stderr(predicate::str::contains("环境变量 RWC_METRICS 有误"));
+    rwc_in(&dir).env("RWC_CONFIG", "missing.toml").arg("l3").assert().code(2)
+        .stderr(predicate::str::contains("无法读取配置文件 missing.toml"));
+    configured().env("RWC_WIDTH", "0").arg("l3").assert().code(2)
+        .stderr(predicate::str::contains("环境变量 RWC_WIDTH 有误"));
+    rwc_in(&dir).args(["--width", "0", "l3"]).assert().code(2);
+
+    // 生成补全脚本和手册页不读取配置
+    rwc_in(&dir).env("RWC_CONFIG", "missing.toml").args(["completions", "bash"]).assert().success();
+    rwc_in(&dir).env("RWC_FORMAT", "yaml").arg("man").assert().success();
+}
+
+#[test]
+fn test_completions_and_man_page() {
+    let mut cmd = rwc();
+    cmd.args(["completions", "zsh"]).assert().success()
+        .stdout(predicate::str::starts_with("#compdef rwc").and(predicate::str::contains("--files0-from")));
+    let mut cmd = rwc();
+    cmd.args(["completions", "fish"]).assert().success().stdout(predicate::str::contains("complete -c rwc"));
+    let mut cmd = rwc();
+    cmd.arg("man").assert().success()
+        .stdout(predicate::str::starts_with(".ie").and(predicate::str::contains(".TH rwc 1")));
+    let mut cmd = rwc();
+    cmd.args(["completions", "tcsh"]).assert().code(2);
+}
+
+#[test]
+fn test_line_terminators_and_records() {
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    std::fs::write(dir.path().join("nul"), b"a b\0c\nd\0e").unwrap();
+    std::fs::write(dir.path().join("cr"), b"one\rtwo three\r").unwrap();
+    std::fs::write(dir.path().join("paragraphs"), b"p1\nstill p1\n\np2\n\n\np3\n").unwrap();
+
+    // 行结束符也分隔单词；末尾没有结束符的最后一行不计入
+    rwc_in(&dir).args(["-z", "nul"]).assert().success().stdout("2 5 9 nul\n");
+    rwc_in(&dir).args(["--line-terminator", "\\x00", "-lL", "nul"]).assert().success().stdout("2 3 nul\n");
+    rwc_in(&dir).args(["--line-terminator", "\\r", "-lw", "cr"]).assert().success().stdout(" 2  3 cr\n");
+    rwc_in(&dir).args(["-l", "--record-separator", r"\n\n+", "paragraphs", "cr"]).assert().success()
+        .stdout(" 3 paragraphs\n 1 cr\n 4 total\n");
+
+    rwc_in(&dir).args(["--line-terminator", "ab", "nul"]).assert().code(2);
+    rwc_in(&dir).args(["--line-terminator", "é", "nul"]).assert().code(2)
+        .stderr(predicate::str::contains("ASCII"));
+    rwc_in(&dir).args(["--record-separator", "\n*", "nul"]).assert().code(2);
+    rwc_in(&dir).args(["-z", "--record-separator", "\n", "nul"]).assert().code(2);
+}
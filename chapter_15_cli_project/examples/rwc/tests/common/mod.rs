@@ -0,0 +1,20 @@
+// 集成测试共用的辅助函数
+
+use assert_cmd::Command;
+use std::path::Path;
+
+// 运行被测的 rwc。不读取开发者的配置文件和 RWC_* 环境变量，结果与运行测试的环境无关
+pub fn rwc() -> Command {
+    Command::from_std(rwc_std())
+}
+
+// 与 `rwc` 相同，但返回标准库的 Command，用于需要在后台运行的测试
+pub fn rwc_std() -> std::process::Command {
+    let mut cmd = std::process::Command::new(assert_cmd::cargo::cargo_bin("rwc"));
+    let home = Path::new(env!("CARGO_TARGET_TMPDIR")).join("home"); // 其中没有配置文件
+    cmd.env("HOME", &home).env("XDG_CONFIG_HOME", home.join(".config"));
+    for name in ["RWC_CONFIG", "RWC_METRICS", "RWC_FORMAT", "RWC_WIDTH"] {
+        cmd.env_remove(name);
+    }
+    cmd
+}
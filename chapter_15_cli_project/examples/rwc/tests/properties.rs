@@ -0,0 +1,150 @@
+// 基于性质的测试：在随机生成的输入上检查统计结果的不变量，并与系统的 GNU wc 做差分比较。
+//
+// 失败时 proptest 会把输入缩小到最简的反例再报告。差分测试只在系统中有 GNU wc
+// 并且支持 C.UTF-8 locale 时运行，否则跳过。
+
+mod common;
+
+use assert_cmd::Command;
+use common::rwc;
+use proptest::prelude::*;
+use rwc::{Counter, Stats};
+use std::io::{Cursor, Write};
+
+// 容易触发边界情况的片段：多字节字符、无效序列、各种空白和控制字符
+const FRAGMENTS: [&[u8]; 16] = [
+    b"word",
+    b"x",
+    b" ",
+    b"  ",
+    b"\n",
+    b"\t",
+    b"\r",
+    b"\x0b",
+    b"\x01",
+    b"\xff",
+    b"\xe4\xbd", // 截断的 "你"
+    b"\x80",
+    "你好".as_bytes(),
+    "\u{3000}".as_bytes(), // 全角空格
+    "\u{a0}".as_bytes(),   // NBSP
+    "caf\u{e9}".as_bytes(),
+];
+
+fn fragments() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(prop::sample::select(&FRAGMENTS[..]), 0..60).prop_map(|parts| parts.concat())
+}
+
+fn input() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![fragments(), prop::collection::vec(any::<u8>(), 0..300)]
+}
+
+// 与 GNU wc 比较用的输入：随机字节中不会出现多字节序列的首字节，有两个已知的、有意保留的差异：
+// - glibc 和 unicode-width 依据的 Unicode 版本不同，随机码位 (例如未分配的 U+07B2) 的可打印性
+//   和宽度本来就可能不一致，所以多字节字符只来自上面的片段；
+// - glibc 仍然把 F8..FD 开头的 5、6 字节旧式序列解码为一个字符，rwc 按 Unicode 的规则视为无效序列。
+fn comparable_input() -> impl Strategy<Value = Vec<u8>> {
+    let byte = prop_oneof![0x00u8..=0x7f, 0x80u8..=0xbf, 0xfeu8..=0xff]; // 单独出现的延续字节总是无效
+    let random = prop::collection::vec(byte, 0..300);
+    prop::collection::vec(prop_oneof![fragments(), random], 1..3).prop_map(|parts| parts.concat())
+}
+
+fn all_metrics() -> Counter {
+    Counter::new().lines(true).words(true).bytes(true).chars(true).graphemes(true).max_line_length(true).invalid(true)
+}
+
+fn count(counter: &Counter, input: &[u8]) -> Stats {
+    counter.count_reader(Cursor::new(input)).unwrap()
+}
+
+proptest! {
+    // 任意切分后逐段交给 Tally，结果与一次统计整个输入相同
+    #[test]
+    fn prop_split_input_matches_whole(input in input(), cuts in prop::collection::vec(any::<prop::sample::Index>(), 0..8)) {
+        for counter in [all_metrics(), all_metrics().unicode_words(true)] {
+            let mut cuts: Vec<usize> = cuts.iter().map(|cut| cut.index(input.len() + 1)).collect();
+            cuts.sort_unstable();
+            let mut tally = counter.tally(None);
+            let mut start = 0;
+            for cut in cuts.into_iter().chain([input.len()]) {
+                tally.update(&input[start..cut]);
+                start = cut;
+            }
+            prop_assert_eq!(tally.finish(), count(&counter, &input));
+        }
+    }
+
+    // 以换行符结束的两段输入分别统计后相加，等于拼接后统计
+    #[test]
+    fn prop_stats_add_across_lines(mut first in input(), second in input()) {
+        first.push(b'\n');
+        let whole = [first.as_slice(), second.as_slice()].concat();
+        let counter = all_metrics();
+        prop_assert_eq!(count(&counter, &first) + count(&counter, &second), count(&counter, &whole));
+    }
+
+    #[test]
+    fn prop_basic_invariants(input in input()) {
+        let stats = count(&all_metrics(), &input);
+        prop_assert_eq!(stats.bytes, input.len());
+        prop_assert_eq!(stats.lines, input.iter().filter(|&&b| b == b'\n').count());
+        prop_assert!(stats.chars <= stats.bytes);
+        prop_assert!(stats.graphemes <= stats.chars);
+        prop_assert!(stats.words <= stats.chars);
+        // 合法的 UTF-8 没有无效序列，字符数与 str 的字符数相同
+        if let Ok(text) = std::str::from_utf8(&input) {
+            prop_assert_eq!(stats.invalid, 0);
+            prop_assert_eq!(stats.chars, text.chars().count());
+        }
+    }
+
+    // 内存映射的快速路径与流式读取的结果相同
+    #[test]
+    fn prop_mmap_matches_streaming(input in input()) {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&input).unwrap();
+        for counter in [all_metrics(), Counter::new().lines(true).bytes(true)] {
+            prop_assert_eq!(counter.count_file(file.path()).unwrap(), count(&counter, &input));
+        }
+    }
+}
+
+// 系统中是否有可以用来比较的 GNU wc：需要支持 UTF-8 的 locale，否则 -m 统计的是字节
+fn gnu_wc_available() -> bool {
+    let output = std::process::Command::new("wc").arg("--version").output();
+    if !output.is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("GNU coreutils")) {
+        return false;
+    }
+    let mut wc = std::process::Command::new("wc");
+    wc.arg("-m").env("LC_ALL", "C.UTF-8");
+    let chars = assert_cmd::Command::from_std(wc).write_stdin("é").output();
+    chars.is_ok_and(|output| output.stdout.trim_ascii() == b"1")
+}
+
+#[test]
+fn test_differential_against_gnu_wc() {
+    if !gnu_wc_available() {
+        eprintln!("跳过：没有找到支持 C.UTF-8 的 GNU wc");
+        return;
+    }
+    let dir = tempfile::tempdir().expect("无法创建临时目录");
+    // 每个用例要运行两个进程，用例数比默认的少一些
+    let config = ProptestConfig { cases: 64, failure_persistence: None, ..ProptestConfig::default() };
+    let mut runner = proptest::test_runner::TestRunner::new(config);
+    runner
+        .run(&(comparable_input(), comparable_input()), |(first, second)| {
+            std::fs::write(dir.path().join("first"), &first).unwrap();
+            std::fs::write(dir.path().join("second"), &second).unwrap();
+            // 两个文件：同时比较各文件的计数、总计和列宽
+            let args = ["-lwmcL", "first", "second"];
+            let run = |mut program: Command| {
+                program.args(args).current_dir(dir.path()).env("LC_ALL", "C.UTF-8").output().unwrap()
+            };
+            let expected = run(Command::new("wc"));
+            let actual = run(rwc());
+            prop_assert_eq!(String::from_utf8_lossy(&actual.stdout), String::from_utf8_lossy(&expected.stdout));
+            prop_assert_eq!(actual.status.code(), expected.status.code());
+            Ok(())
+        })
+        .unwrap();
+}
@@ -0,0 +1,245 @@
+// 服务器的设置。
+//
+// 优先级从高到低：命令行参数、环境变量、配置文件、内置默认值。
+// - 配置文件：`--config` 或 $SIMPLE_API_CONFIG 指定的 TOML 文件 (没有指定时不读取配置文件)；
+// - 环境变量：SIMPLE_API_ 加上大写的设置名，例如 SIMPLE_API_PORT、SIMPLE_API_LOG_LEVEL。
+//
+// 配置文件示例 (所有设置都可以省略)：
+//
+//     host = "0.0.0.0"                         # 监听的地址
+//     port = 8080                              # 监听的端口
+//     # bind = "0.0.0.0:8080"                  # 同时设置地址和端口，不能与 host、port 写在同一处
+//     log_level = "debug"                      # error、warn、info、debug、trace
+//     body_limit = 1048576                     # 请求体的最大字节数
+//     request_timeout = 30                     # 处理一个请求的最长时间 (秒)
+//     shutdown_timeout = 10                    # 关闭时等待进行中请求的最长时间 (秒)
+//     database_url = "sqlite://simple_api.db"  # /items 的数据库
+//
+// 每一处来源 (配置文件、环境变量、命令行) 中的设置都会被检查，即使被更高优先级的来源覆盖：
+// 写错的设置总是报告出来，而不是被悄悄忽略。
+//
+// bind 只是同时设置 host 和 port 的简写。同一处来源中不能既有 bind 又有 host 或 port (无法判断
+// 哪一个是本意)；不同来源之间按优先级逐项覆盖，与其他设置相同：例如配置文件中的 bind = "0.0.0.0:4000"
+// 加上环境变量 SIMPLE_API_PORT=5000 得到 0.0.0.0:5000，而环境变量中的 bind 覆盖配置文件中的 host 和 port。
+
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+
+/// 命令行参数。没有给出的设置由环境变量、配置文件和默认值补充
+#[derive(Parser, Debug, Default)]
+#[clap(name = "simple_api", version, about = "一个使用 Axum 和 Tokio 构建的简单 Web API 服务")]
+pub struct Cli {
+    #[clap(long, value_name = "FILE", help = "读取 TOML 配置文件 (也可以用 SIMPLE_API_CONFIG 指定)")]
+    pub config: Option<PathBuf>,
+
+    #[clap(long, value_name = "ADDR:PORT", conflicts_with_all = ["host", "port"], help = "监听的地址和端口，例如 0.0.0.0:8080")]
+    pub bind: Option<SocketAddr>,
+
+    #[clap(long, value_name = "ADDR", help = "监听的地址 [默认: 127.0.0.1]")]
+    pub host: Option<IpAddr>,
+
+    #[clap(short, long, help = "监听的端口 [默认: 3000]")]
+    pub port: Option<u16>,
+
+    #[clap(long, value_enum, help = "日志级别 [默认: info]")]
+    pub log_level: Option<LogLevel>,
+
+    #[clap(long, value_name = "BYTES", help = "请求体的最大字节数 [默认: 2097152]")]
+    pub body_limit: Option<usize>,
+
+    #[clap(long, value_name = "SECS", help = "处理一个请求的最长时间 (秒) [默认: 30]")]
+    pub request_timeout: Option<u64>,
+
+    #[clap(long, value_name = "SECS", help = "关闭时等待进行中请求的最长时间 (秒) [默认: 10]")]
+    pub shutdown_timeout: Option<u64>,
+
+    #[clap(long, value_name = "URL", help = "/items 使用的数据库 [默认: sqlite://simple_api.db]")]
+    pub database_url: Option<String>,
+}
+
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// 合并之后的有效设置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub host: IpAddr,
+    pub port: u16,
+    pub log_level: LogLevel,
+    /// 请求体的最大字节数，超过时返回 413
+    pub body_limit: usize,
+    /// 超过时返回 408
+    pub request_timeout: Duration,
+    /// 收到关闭信号后，超过这个时间仍未完成的请求被放弃
+    pub shutdown_timeout: Duration,
+    pub database_url: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 3000,
+            log_level: LogLevel::Info,
+            body_limit: 2 * 1024 * 1024, // 与 axum 的默认值相同
+            request_timeout: Duration::from_secs(30),
+            shutdown_timeout: Duration::from_secs(10),
+            database_url: "sqlite://simple_api.db".to_string(),
+        }
+    }
+}
+
+// 一处来源中的设置，没有出现的为 None
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    bind: Option<SocketAddr>,
+    host: Option<IpAddr>,
+    port: Option<u16>,
+    log_level: Option<LogLevel>,
+    body_limit: Option<usize>,
+    request_timeout: Option<u64>,
+    shutdown_timeout: Option<u64>,
+    database_url: Option<String>,
+}
+
+impl Config {
+    /// 读取 `cli` 指定的配置文件和环境变量，与 `cli` 合并；出错时返回说明出错位置的信息
+    pub fn load(cli: &Cli) -> Result<Config, String> {
+        let lookup = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let file = match cli.config.clone().or_else(|| lookup("SIMPLE_API_CONFIG").map(PathBuf::from)) {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path).map_err(|e| format!("无法读取配置文件 {}: {}", path.display(), e))?;
+                Some((path, text))
+            }
+            None => None,
+        };
+        Config::from_sources(cli, file, lookup)
+    }
+
+    /// 与 `load` 相同，但配置文件的内容和环境变量由调用者提供
+    pub fn from_sources(
+        cli: &Cli,
+        file: Option<(PathBuf, String)>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Config, String> {
+        let mut config = Config::default();
+        if let Some((path, text)) = file {
+            let source = format!("配置文件 {}", path.display());
+            let settings = toml::from_str(&text).map_err(|e| format!("{} 有误: {}", source, e))?;
+            config.apply(settings, &source)?;
+        }
+        config.apply(env_settings(env)?, "环境变量")?;
+        config.apply(cli_settings(cli), "命令行")?;
+        Ok(config)
+    }
+
+    /// 监听的地址和端口
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+
+    // 用一处来源中出现的设置覆盖当前的值
+    fn apply(&mut self, settings: Settings, source: &str) -> Result<(), String> {
+        if settings.bind.is_some() && (settings.host.is_some() || settings.port.is_some()) {
+            return Err(format!("{}: bind 不能与 host 或 port 同时设置", source));
+        }
+        if settings.body_limit == Some(0) {
+            return Err(format!("{}: body_limit 必须大于 0", source));
+        }
+        if settings.request_timeout == Some(0) {
+            return Err(format!("{}: request_timeout 必须大于 0", source));
+        }
+        if settings.database_url.as_deref().is_some_and(|url| url.trim().is_empty()) {
+            return Err(format!("{}: database_url 不能为空", source));
+        }
+
+        if let Some(bind) = settings.bind {
+            self.host = bind.ip();
+            self.port = bind.port();
+        }
+        if let Some(host) = settings.host {
+            self.host = host;
+        }
+        if let Some(port) = settings.port {
+            self.port = port;
+        }
+        if let Some(log_level) = settings.log_level {
+            self.log_level = log_level;
+        }
+        if let Some(body_limit) = settings.body_limit {
+            self.body_limit = body_limit;
+        }
+        if let Some(secs) = settings.request_timeout {
+            self.request_timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = settings.shutdown_timeout {
+            self.shutdown_timeout = Duration::from_secs(secs);
+        }
+        if let Some(database_url) = settings.database_url {
+            self.database_url = database_url;
+        }
+        Ok(())
+    }
+}
+
+fn env_settings(env: impl Fn(&str) -> Option<String>) -> Result<Settings, String> {
+    fn parse<T: FromStr<Err: Display>>(env: &impl Fn(&str) -> Option<String>, name: &str) -> Result<Option<T>, String> {
+        env(name)
+            .map(|value| value.trim().parse().map_err(|e| format!("环境变量 {} 有误: {} ('{}')", name, e, value)))
+            .transpose()
+    }
+
+    let log_level = env("SIMPLE_API_LOG_LEVEL")
+        .map(|value| LogLevel::from_str(value.trim(), true).map_err(|e| format!("环境变量 SIMPLE_API_LOG_LEVEL 有误: {}", e)))
+        .transpose()?;
+    Ok(Settings {
+        bind: parse(&env, "SIMPLE_API_BIND")?,
+        host: parse(&env, "SIMPLE_API_HOST")?,
+        port: parse(&env, "SIMPLE_API_PORT")?,
+        log_level,
+        body_limit: parse(&env, "SIMPLE_API_BODY_LIMIT")?,
+        request_timeout: parse(&env, "SIMPLE_API_REQUEST_TIMEOUT")?,
+        shutdown_timeout: parse(&env, "SIMPLE_API_SHUTDOWN_TIMEOUT")?,
+        database_url: env("SIMPLE_API_DATABASE_URL"),
+    })
+}
+
+fn cli_settings(cli: &Cli) -> Settings {
+    Settings {
+        bind: cli.bind,
+        host: cli.host,
+        port: cli.port,
+        log_level: cli.log_level,
+        body_limit: cli.body_limit,
+        request_timeout: cli.request_timeout,
+        shutdown_timeout: cli.shutdown_timeout,
+        database_url: cli.database_url.clone(),
+    }
+}
@@ -0,0 +1,184 @@
+// 处理函数的错误类型。
+//
+// 所有错误响应 (包括提取器的拒绝和 fallback) 都是 RFC 7807 的 problem+json：
+// `{"type": "about:blank", "title": "Not Found", "status": 404, "detail": "..."}`。
+// 校验错误另外带有 `errors`：每个不合法字段一项，`field` 是字段的路径 (例如 `address.city`、`tags[1]`)
+
+use crate::repository::RepositoryError;
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+/// problem+json 响应的媒体类型
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+#[derive(Debug)]
+pub enum AppError {
+    /// 请求的内容不合法 (422)。`errors` 为空时只有 `detail`，例如缺少字段或类型错误
+    Validation { detail: String, errors: Vec<FieldError> },
+    /// 资源不存在 (404)
+    NotFound(String),
+    /// 与现有资源冲突，例如重复的名称 (409)
+    Conflict(String),
+    /// 服务器内部错误 (500)。说明只记录到服务器日志，不返回给客户端
+    Internal(String),
+    /// 其他无法处理的请求：格式错误的 JSON、错误的内容类型、不允许的方法等
+    Rejected(StatusCode, String),
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Rejected(status, _) => *status,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Validation { detail, .. }
+            | AppError::NotFound(detail)
+            | AppError::Conflict(detail)
+            | AppError::Internal(detail)
+            | AppError::Rejected(_, detail) => f.write_str(detail),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// RFC 7807 的错误响应体
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// 一个字段的校验错误
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    /// 违反的规则，例如 `length`、`range`、`regex`
+    pub code: String,
+    pub message: String,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let (detail, errors) = match self {
+            AppError::Internal(message) => {
+                tracing::error!("服务器内部错误: {}", message); // 记录到服务器日志
+                ("服务器内部错误".to_string(), Vec::new())
+            }
+            AppError::Validation { detail, errors } => (detail, errors),
+            AppError::NotFound(detail) | AppError::Conflict(detail) | AppError::Rejected(_, detail) => (detail, Vec::new()),
+        };
+        let problem = Problem {
+            type_: "about:blank".to_string(), // 除状态码外没有更多语义
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail,
+            errors,
+        };
+        (status, [(header::CONTENT_TYPE, PROBLEM_JSON)], Json(problem)).into_response()
+    }
+}
+
+impl From<RepositoryError> for AppError {
+    fn from(e: RepositoryError) -> Self {
+        match e {
+            RepositoryError::Conflict(message) => AppError::Conflict(message),
+            RepositoryError::Database(e) => AppError::Internal(format!("数据库错误: {}", e)),
+        }
+    }
+}
+
+// 提取器的拒绝保留 axum 选择的状态码 (400、413、415 等)，只有 JSON 结构正确但内容不符合时是 Validation
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(e) => AppError::Validation { detail: e.body_text(), errors: Vec::new() },
+            rejection => AppError::Rejected(rejection.status(), rejection.body_text()),
+        }
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        AppError::Rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::Rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        collect_field_errors("", &errors, &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field)); // ValidationErrors 是 HashMap，排序使响应稳定
+        AppError::Validation { detail: "请求内容未通过校验".to_string(), errors: fields }
+    }
+}
+
+// 把嵌套的 ValidationErrors 展开成带路径的字段错误列表
+fn collect_field_errors(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+    for (name, kind) in errors.errors() {
+        let path = if prefix.is_empty() { name.to_string() } else { format!("{}.{}", prefix, name) };
+        match kind {
+            ValidationErrorsKind::Field(errors) => out.extend(errors.iter().map(|error| FieldError {
+                field: path.clone(),
+                code: error.code.to_string(),
+                message: error_message(error),
+            })),
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(&path, errors, out),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_field_errors(&format!("{}[{}]", path, index), errors, out);
+                }
+            }
+        }
+    }
+}
+
+// 规则上没有写 message 时，根据规则和参数生成说明
+fn error_message(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).map(|value| value.to_string());
+    if let ("length", Some(equal)) = (error.code.as_ref(), param("equal")) {
+        return format!("长度必须为 {}", equal);
+    }
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("length", Some(min), Some(max)) => format!("长度必须在 {} 到 {} 之间", min, max),
+        ("length", Some(min), None) => format!("长度不能小于 {}", min),
+        ("length", None, Some(max)) => format!("长度不能大于 {}", max),
+        ("range", Some(min), Some(max)) => format!("必须在 {} 到 {} 之间", min, max),
+        ("range", Some(min), None) => format!("不能小于 {}", min),
+        ("range", None, Some(max)) => format!("不能大于 {}", max),
+        ("regex", _, _) => "格式不正确".to_string(),
+        (code, _, _) => format!("不满足规则 {}", code),
+    }
+}
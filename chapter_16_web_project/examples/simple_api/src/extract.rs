@@ -0,0 +1,55 @@
+// axum 提取器的包装：行为相同，但拒绝时返回 `AppError` (problem+json)，而不是 axum 的纯文本响应。
+// `ValidatedJson` 在解析 JSON 之后还会运行请求结构体上声明的校验规则 (`validator::Validate`)。
+
+use crate::error::AppError;
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Request},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+/// 同 `axum::Json`，也可以作为响应
+#[derive(FromRequest, Debug, Clone, Copy, Default)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct Json<T>(pub T);
+
+impl<T> IntoResponse for Json<T>
+where
+    axum::Json<T>: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// 同 `axum::extract::Path`
+#[derive(FromRequestParts, Debug)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct Path<T>(pub T);
+
+/// 同 `axum::extract::Query`
+#[derive(FromRequestParts, Debug)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct Query<T>(pub T);
+
+/// 解析 JSON 请求体并校验：`#[derive(Validate)]` 上声明的规则 (长度、范围、正则、嵌套结构等)
+/// 不满足时返回 422，响应体的 `errors` 列出每个不合法的字段
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        value.validate()?;
+        Ok(ValidatedJson(value))
+    }
+}
@@ -0,0 +1,107 @@
+// 路由处理函数
+
+use crate::error::AppError;
+use crate::extract::{Json, Path, Query, ValidatedJson};
+use crate::models::{EchoPayload, GreetingResponse, Item, ItemPage, ItemPatch, NewItem, Pagination};
+use crate::AppState;
+use axum::{
+    extract::State,
+    http::{header, Method, StatusCode, Uri},
+    response::{Html, IntoResponse},
+};
+
+// --- 路由处理函数 (Handlers) ---
+
+// GET /
+pub async fn root_handler() -> Html<&'static str> {
+    tracing::info!("处理 GET / 请求");
+    Html("<h1>欢迎来到 Axum 简单 API 服务!</h1><p>尝试访问 /hello, /greet/:name, /items, 或 POST 到 /echo_json</p>")
+}
+
+// GET /hello
+pub async fn hello_handler() -> Json<GreetingResponse> {
+    tracing::info!("处理 GET /hello 请求");
+    Json(GreetingResponse {
+        greeting: "Hello, Web from Axum!".to_string(),
+    })
+}
+
+// GET /greet/:name
+pub async fn greet_handler(Path(name): Path<String>) -> Json<GreetingResponse> {
+    tracing::info!("处理 GET /greet/{} 请求", name);
+    Json(GreetingResponse {
+        greeting: format!("Hello, {}!", name),
+    })
+}
+
+// POST /echo_json：message 不能为空，count 不能为负
+pub async fn echo_json_handler(ValidatedJson(payload): ValidatedJson<EchoPayload>) -> Json<EchoPayload> {
+    tracing::info!("处理 POST /echo_json 请求, payload: {:?}", payload);
+    Json(payload) // 直接将解析后的 payload 返回为 JSON
+}
+
+// --- /items ---
+
+fn not_found(id: i64) -> AppError {
+    AppError::NotFound(format!("条目 {} 不存在", id))
+}
+
+// GET /items?limit=&offset=
+pub async fn list_items(State(state): State<AppState>, Query(page): Query<Pagination>) -> Result<Json<ItemPage>, AppError> {
+    let (items, total) = state.items.list(page).await?;
+    Ok(Json(ItemPage { items, total, limit: page.limit.min(Pagination::MAX_LIMIT), offset: page.offset }))
+}
+
+// GET /items/:id
+pub async fn get_item(State(state): State<AppState>, Path(id): Path<i64>) -> Result<Json<Item>, AppError> {
+    let item = state.items.get(id).await?;
+    item.map(Json).ok_or_else(|| not_found(id))
+}
+
+// POST /items：返回 201 和新条目的位置
+pub async fn create_item(
+    State(state): State<AppState>,
+    ValidatedJson(new_item): ValidatedJson<NewItem>,
+) -> Result<impl IntoResponse, AppError> {
+    let item = state.items.create(new_item).await?;
+    let location = format!("/items/{}", item.id);
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(item)))
+}
+
+// PUT /items/:id
+pub async fn update_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ValidatedJson(new_item): ValidatedJson<NewItem>,
+) -> Result<Json<Item>, AppError> {
+    let item = state.items.update(id, new_item).await?;
+    item.map(Json).ok_or_else(|| not_found(id))
+}
+
+// PATCH /items/:id
+pub async fn patch_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ValidatedJson(patch): ValidatedJson<ItemPatch>,
+) -> Result<Json<Item>, AppError> {
+    let item = state.items.patch(id, patch).await?;
+    item.map(Json).ok_or_else(|| not_found(id))
+}
+
+// DELETE /items/:id：成功时返回 204
+pub async fn delete_item(State(state): State<AppState>, Path(id): Path<i64>) -> Result<StatusCode, AppError> {
+    match state.items.delete(id).await? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(not_found(id)),
+    }
+}
+
+// Fallback 处理器 (404 Not Found)
+pub async fn handler_404(uri: Uri) -> AppError {
+    AppError::NotFound(format!("路径 {} 不存在", uri.path()))
+}
+
+// 路径存在但不支持该方法 (405)；axum 会另外加上 Allow 头
+pub async fn handler_405(method: Method, uri: Uri) -> AppError {
+    AppError::Rejected(StatusCode::METHOD_NOT_ALLOWED, format!("{} 不支持 {} 方法", uri.path(), method))
+}
@@ -0,0 +1,100 @@
+//! simple_api 的路由和服务器启动逻辑。
+//!
+//! `main.rs` 只负责读取设置 ([`config`])、监听端口并调用 [`serve`]；测试直接对 [`app`] 返回的 `Router`
+//! 发送请求 (`tower::ServiceExt::oneshot`)，测试的就是实际运行的代码，不需要真正的网络连接。
+
+use axum::{
+    extract::{DefaultBodyLimit, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use config::Config;
+use error::AppError;
+use repository::ItemRepository;
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+pub mod config;
+pub mod error;
+pub mod extract;
+pub mod handlers;
+pub mod models;
+pub mod repository;
+
+/// 所有处理函数共享的应用状态
+#[derive(Clone)]
+pub struct AppState {
+    pub items: Arc<dyn ItemRepository>,
+}
+
+impl AppState {
+    pub fn new(items: impl ItemRepository + 'static) -> Self {
+        AppState { items: Arc::new(items) }
+    }
+}
+
+/// 构建应用的路由；`config` 提供请求体大小和处理时间的限制
+pub fn app(state: AppState, config: &Config) -> Router {
+    Router::new()
+        .route("/", get(handlers::root_handler))
+        .route("/hello", get(handlers::hello_handler))
+        .route("/greet/:name", get(handlers::greet_handler))
+        .route("/echo_json", post(handlers::echo_json_handler))
+        .route("/items", get(handlers::list_items).post(handlers::create_item))
+        .route(
+            "/items/:id",
+            get(handlers::get_item)
+                .put(handlers::update_item)
+                .patch(handlers::patch_item)
+                .delete(handlers::delete_item),
+        )
+        .fallback(handlers::handler_404) // 添加一个 404 fallback处理器
+        .method_not_allowed_fallback(handlers::handler_405)
+        .layer(DefaultBodyLimit::max(config.body_limit))
+        .layer(middleware::from_fn_with_state(config.request_timeout, request_timeout))
+        // .layer(tower_http::trace::TraceLayer::new_for_http()); // 可选的 HTTP 请求追踪中间件
+        .with_state(state)
+}
+
+// 处理时间超过 `limit` 的请求返回 408
+async fn request_timeout(State(limit): State<Duration>, request: Request, next: Next) -> Response {
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => AppError::Rejected(StatusCode::REQUEST_TIMEOUT, format!("请求没有在 {} 秒内处理完", limit.as_secs()))
+            .into_response(),
+    }
+}
+
+/// 在 `listener` 上运行服务，直到 `shutdown` 完成后优雅关闭：等待进行中的请求处理完毕，
+/// 但最多等待 `config.shutdown_timeout`
+pub async fn serve<F>(listener: TcpListener, state: AppState, config: Config, shutdown: F) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (closing_tx, closing_rx) = oneshot::channel();
+    let server = axum::serve(listener, app(state, &config).into_make_service()).with_graceful_shutdown(async move {
+        shutdown.await;
+        let _ = closing_tx.send(());
+    });
+    // 服务器没有收到关闭信号就结束时 closing_rx 出错，这时只等服务器的结果
+    let deadline = async move {
+        match closing_rx.await {
+            Ok(()) => tokio::time::sleep(config.shutdown_timeout).await,
+            Err(_) => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        result = server => result,
+        () = deadline => {
+            tracing::warn!("{} 秒内仍有未完成的请求，不再等待", config.shutdown_timeout.as_secs());
+            Ok(())
+        }
+    }
+}
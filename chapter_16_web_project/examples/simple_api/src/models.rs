@@ -0,0 +1,113 @@
+// 请求和响应的 JSON 数据结构
+
+use serde::{Deserialize, Deserializer, Serialize};
+use validator::{Validate, ValidationError};
+
+// --- /echo_json 和问候 ---
+#[derive(Serialize, Deserialize, Validate, Debug, Clone, PartialEq)] // Clone 和 PartialEq 用于测试时的方便
+pub struct EchoPayload {
+    #[validate(length(min = 1, message = "消息不能为空"))]
+    pub message: String,
+    #[validate(range(min = 0, message = "计数不能为负"))]
+    pub count: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct GreetingResponse {
+    pub greeting: String,
+}
+
+// --- /items ---
+
+/// 存储的条目。时间为 UTC 的 RFC 3339 字符串，由数据库生成
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Item {
+    pub id: i64,
+    pub name: String, // 唯一
+    pub description: Option<String>,
+    pub quantity: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// POST /items 和 PUT /items/:id 的请求体：PUT 整体替换，省略的可选字段恢复为默认值
+#[derive(Serialize, Deserialize, Validate, Debug, Clone, PartialEq)]
+pub struct NewItem {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[serde(default)]
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub quantity: i64,
+}
+
+/// PATCH /items/:id 的请求体：只修改出现的字段。
+/// `name` 和 `description` 区分 "没有出现" (None) 和 "设为 null" (Some(None))；
+/// 条目总要有名称，所以 `name` 不能为 null。规则与 `NewItem` 相同，只检查出现的字段
+#[derive(Serialize, Deserialize, Validate, Debug, Clone, Default, PartialEq)]
+pub struct ItemPatch {
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(min = 1, max = 100), custom(function = "not_null"))]
+    pub name: Option<Nullable<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(max = 1000))]
+    pub description: Option<Nullable<String>>,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub quantity: Option<i64>,
+}
+
+/// PATCH 请求体中出现的字段的值，null 为 None。
+/// validator 只剥掉字面上写作 `Option` 的那一层，所以 `custom` 规则收到的是这一层，能够区分 null
+pub type Nullable<T> = Option<T>;
+
+fn not_null(value: &Nullable<String>) -> Result<(), ValidationError> {
+    match value {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("required").with_message("不能为 null".into())),
+    }
+}
+
+// 字段出现时 (包括值为 null) 总是 Some，没有出现时由 `default` 得到 None
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// GET /items 的分页参数
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Pagination {
+    #[serde(default = "Pagination::default_limit")]
+    pub limit: u32,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+impl Pagination {
+    /// 每页最多返回的条目数，更大的 limit 会被截断
+    pub const MAX_LIMIT: u32 = 100;
+
+    fn default_limit() -> u32 {
+        20
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination { limit: Pagination::default_limit(), offset: 0 }
+    }
+}
+
+/// GET /items 的响应：一页条目和条目总数
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ItemPage {
+    pub items: Vec<Item>,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+}
@@ -0,0 +1,164 @@
+// /items 的存储。
+//
+// 处理函数只依赖 `ItemRepository` trait；`SqliteItemRepository` 是基于 SQLite 的实现，
+// 连接时运行 migrations/ 下的迁移。测试使用同一个实现的内存数据库 (`in_memory`)，
+// 每个测试都得到一个独立的空数据库。
+
+use crate::models::{Item, ItemPatch, NewItem, Pagination};
+use async_trait::async_trait;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    /// 违反唯一约束，例如重复的条目名称
+    Conflict(String),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepositoryError::Conflict(message) => write!(f, "冲突: {}", message),
+            RepositoryError::Database(e) => write!(f, "数据库错误: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => RepositoryError::Conflict(db.message().to_string()),
+            _ => RepositoryError::Database(e),
+        }
+    }
+}
+
+/// 条目的存储。`get`、`update`、`patch` 在条目不存在时返回 `Ok(None)`
+#[async_trait]
+pub trait ItemRepository: Send + Sync {
+    /// 按 id 升序返回一页条目，以及条目总数
+    async fn list(&self, page: Pagination) -> Result<(Vec<Item>, u64), RepositoryError>;
+    async fn get(&self, id: i64) -> Result<Option<Item>, RepositoryError>;
+    async fn create(&self, item: NewItem) -> Result<Item, RepositoryError>;
+    /// 整体替换
+    async fn update(&self, id: i64, item: NewItem) -> Result<Option<Item>, RepositoryError>;
+    /// 只修改 `patch` 中出现的字段
+    async fn patch(&self, id: i64, patch: ItemPatch) -> Result<Option<Item>, RepositoryError>;
+    /// 返回条目是否存在 (并被删除)
+    async fn delete(&self, id: i64) -> Result<bool, RepositoryError>;
+}
+
+const COLUMNS: &str = "id, name, description, quantity, created_at, updated_at";
+
+// 与 migrations/ 中的默认值相同的时间格式
+const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')";
+
+#[derive(Debug, Clone)]
+pub struct SqliteItemRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteItemRepository {
+    /// 连接 `url` 指定的数据库 (例如 `sqlite://simple_api.db`)，文件不存在时创建，然后运行迁移
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+        Self::migrate(pool).await
+    }
+
+    /// 独立的内存数据库，用于测试
+    pub async fn in_memory() -> Result<Self, sqlx::Error> {
+        // 每个连接都有自己的内存数据库，所以连接池只能有一个连接，并且不能被回收
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?)
+            .await?;
+        Self::migrate(pool).await
+    }
+
+    async fn migrate(pool: SqlitePool) -> Result<Self, sqlx::Error> {
+        sqlx::migrate!().run(&pool).await?;
+        Ok(SqliteItemRepository { pool })
+    }
+}
+
+#[async_trait]
+impl ItemRepository for SqliteItemRepository {
+    async fn list(&self, page: Pagination) -> Result<(Vec<Item>, u64), RepositoryError> {
+        // 在同一个事务中读取，总数与这一页的条目一致 (两次查询之间的写入不会只反映在其中一个里)
+        let mut tx = self.pool.begin().await?;
+        let items = sqlx::query_as(&format!("SELECT {} FROM items ORDER BY id LIMIT ? OFFSET ?", COLUMNS))
+            .bind(page.limit.min(Pagination::MAX_LIMIT))
+            .bind(page.offset)
+            .fetch_all(&mut *tx)
+            .await?;
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items").fetch_one(&mut *tx).await?;
+        tx.commit().await?;
+        Ok((items, total as u64))
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<Item>, RepositoryError> {
+        let item = sqlx::query_as(&format!("SELECT {} FROM items WHERE id = ?", COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(item)
+    }
+
+    async fn create(&self, item: NewItem) -> Result<Item, RepositoryError> {
+        let item = sqlx::query_as(&format!(
+            "INSERT INTO items (name, description, quantity) VALUES (?, ?, ?) RETURNING {}",
+            COLUMNS
+        ))
+        .bind(item.name)
+        .bind(item.description)
+        .bind(item.quantity)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(item)
+    }
+
+    async fn update(&self, id: i64, item: NewItem) -> Result<Option<Item>, RepositoryError> {
+        let item = sqlx::query_as(&format!(
+            "UPDATE items SET name = ?, description = ?, quantity = ?, updated_at = {} WHERE id = ? RETURNING {}",
+            NOW, COLUMNS
+        ))
+        .bind(item.name)
+        .bind(item.description)
+        .bind(item.quantity)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(item)
+    }
+
+    async fn patch(&self, id: i64, patch: ItemPatch) -> Result<Option<Item>, RepositoryError> {
+        // 没有出现的字段保持原值；description 出现时 (包括 null) 才修改
+        let item = sqlx::query_as(&format!(
+            "UPDATE items SET name = COALESCE(?, name), \
+             description = CASE WHEN ? THEN ? ELSE description END, \
+             quantity = COALESCE(?, quantity), updated_at = {} \
+             WHERE id = ? RETURNING {}",
+            NOW, COLUMNS
+        ))
+        .bind(patch.name.flatten())
+        .bind(patch.description.is_some())
+        .bind(patch.description.flatten())
+        .bind(patch.quantity)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(item)
+    }
+
+    async fn delete(&self, id: i64) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM items WHERE id = ?").bind(id).execute(&self.pool).await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,234 +1,453 @@
 // tests/api_tests.rs
-
-// 导入需要测试的 crate 中的项 (如果需要，但通常集成测试是黑盒的)
-// use simple_api::*; // 通常不需要，因为我们通过 HTTP 调用
-
-use reqwest::blocking::Client; // 使用 reqwest 的阻塞客户端进行简单测试
-use reqwest::StatusCode;
-use serde_json::{json, Value as JsonValue}; // 用于构建和比较 JSON
+//
+// 用 `tower::ServiceExt::oneshot` 直接把请求交给 `simple_api::app` 返回的真实路由，
+// 在内存中完成，不需要启动服务器；`serve` 的启动和优雅关闭单独用一个真实的 TCP 连接测试。
+// 每个测试使用自己的内存 SQLite 数据库，互不影响。
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::post;
+use axum::Router;
+use regex::Regex;
+use serde::Deserialize;
+use http_body_util::BodyExt; // for `collect`
+use serde_json::{json, Value};
+use simple_api::config::Config;
+use simple_api::error::{FieldError, Problem, PROBLEM_JSON};
+use simple_api::extract::{Json, ValidatedJson};
+use simple_api::models::{EchoPayload, GreetingResponse, Item, ItemPage, ItemPatch, NewItem, Pagination};
+use simple_api::repository::{ItemRepository, RepositoryError, SqliteItemRepository};
+use simple_api::{app, AppState};
+use std::sync::LazyLock;
 use std::time::Duration;
-use std::net::SocketAddr;
 use tokio::sync::oneshot; // 用于优雅关闭服务器
+use tower::ServiceExt; // for `oneshot`
+use validator::Validate;
 
-// 从 main.rs 复制或引用关键的结构体定义，以便测试时使用
-// 更好的方式是如果这些结构体在 lib.rs 中定义，则可以直接 use crate_name::StructName
-// 但由于这是个 bin crate，我们这里可以重新定义或假设它们与 main.rs 一致
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
-struct EchoPayload {
-    message: String,
-    count: i32,
+async fn test_state() -> AppState {
+    AppState::new(SqliteItemRepository::in_memory().await.unwrap())
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
-struct GreetingResponse {
-    greeting: String,
+// 使用空数据库和默认设置的应用；同一个测试中的多个请求共享它 (Router 的 clone 共享状态)
+async fn test_app() -> Router {
+    app(test_state().await, &Config::default())
 }
 
+// 把一个请求交给应用处理并返回响应
+async fn send(request: Request<Body>) -> Response {
+    test_app().await.oneshot(request).await.unwrap()
+}
 
-// 辅助函数：在后台启动服务器并返回其地址和关闭句柄
-// 注意：这个函数本身是 async 的，所以测试函数也需要是 async
-async fn spawn_test_server() -> (SocketAddr, oneshot::Sender<()>) {
-    let (tx, rx) = oneshot::channel(); // 用于发送关闭信号
+fn get(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
 
-    // 从 main.rs 复制路由逻辑，或者将其提取到 lib.rs 中共享
-    // 为了简单，这里直接复制路由定义
-    use axum::{
-        routing::{get, post},
-        Router,
-        Json,
-        extract::Path,
-        response::Html,
-        http::StatusCode as AxumStatusCode, // 避免与 reqwest::StatusCode 冲突
-        response::IntoResponse,
-    };
+fn json_request(method: &str, uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
 
-    async fn root_handler_test() -> Html<&'static str> {
-        Html("<h1>欢迎来到 Axum 简单 API 服务!</h1><p>尝试访问 /hello, /greet/:name, 或 POST 到 /echo_json</p>")
-    }
-    async fn hello_handler_test() -> Json<GreetingResponse> {
-        Json(GreetingResponse { greeting: "Hello, Web from Axum!".to_string() })
-    }
-    async fn greet_handler_test(Path(name): Path<String>) -> Json<GreetingResponse> {
-        Json(GreetingResponse { greeting: format!("Hello, {}!", name) })
-    }
-    async fn echo_json_handler_test(Json(payload): Json<EchoPayload>) -> Json<EchoPayload> {
-        Json(payload)
-    }
-    async fn handler_404_test() -> impl IntoResponse {
-        (AxumStatusCode::NOT_FOUND, Html("<h2>404: 页面未找到</h2>"))
-    }
+fn field_error(field: &str, code: &str, message: &str) -> FieldError {
+    FieldError { field: field.to_string(), code: code.to_string(), message: message.to_string() }
+}
 
-    let app = Router::new()
-        .route("/", get(root_handler_test))
-        .route("/hello", get(hello_handler_test))
-        .route("/greet/:name", get(greet_handler_test))
-        .route("/echo_json", post(echo_json_handler_test))
-        .fallback(handler_404_test);
+// 创建一个条目并返回它
+async fn create(app: &Router, body: Value) -> Item {
+    let response = app.clone().oneshot(json_request("POST", "/items", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    serde_json::from_str(&body_text(response).await).unwrap()
+}
 
-    // 监听一个随机可用端口
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let addr = listener.local_addr().unwrap();
-    println!("测试服务器正在监听: {}", addr);
-
-    tokio::spawn(async move {
-        axum::serve(listener, app.into_make_service())
-            .with_graceful_shutdown(async {
-                rx.await.ok(); // 等待关闭信号
-                println!("测试服务器 (addr: {}) 正在关闭...", addr);
-            })
-            .await
-            .unwrap();
-    });
+fn content_type(response: &Response) -> &str {
+    response.headers().get(header::CONTENT_TYPE).unwrap().to_str().unwrap()
+}
 
-    // 给服务器一点时间启动
-    tokio::time::sleep(Duration::from_millis(100)).await;
+async fn body_text(response: Response) -> String {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
 
-    (addr, tx)
+// 检查错误响应是 problem+json，状态码与响应体一致
+async fn problem(response: Response) -> Problem {
+    assert_eq!(content_type(&response), PROBLEM_JSON);
+    let status = response.status();
+    let problem: Problem = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(problem.status, status.as_u16());
+    assert_eq!(problem.title, status.canonical_reason().unwrap());
+    assert_eq!(problem.type_, "about:blank");
+    problem
 }
 
+#[tokio::test]
+async fn test_root_endpoint() {
+    let response = send(get("/")).await;
 
-#[tokio::test] // 使用 tokio 的测试宏
-async fn test_root_endpoint() -> anyhow::Result<()> {
-    let (addr, shutdown_tx) = spawn_test_server().await;
-    let client = Client::new();
-    let url = format!("http://{}", addr);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(content_type(&response).contains("text/html"));
+    assert!(body_text(response).await.contains("欢迎来到 Axum 简单 API 服务!"));
+}
 
-    let response = client.get(&url).send().await?;
+#[tokio::test]
+async fn test_hello_endpoint() {
+    let response = send(get("/hello")).await;
 
     assert_eq!(response.status(), StatusCode::OK);
-    assert!(response.headers().get("content-type").unwrap().to_str()?.contains("text/html"));
-    let body = response.text().await?;
-    assert!(body.contains("欢迎来到 Axum 简单 API 服务!"));
-
-    let _ = shutdown_tx.send(()); // 发送关闭信号
-    Ok(())
+    assert!(content_type(&response).contains("application/json"));
+    let greeting: GreetingResponse = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(greeting, GreetingResponse { greeting: "Hello, Web from Axum!".to_string() });
 }
 
 #[tokio::test]
-async fn test_hello_endpoint() -> anyhow::Result<()> {
-    let (addr, shutdown_tx) = spawn_test_server().await;
-    let client = Client::new();
-    let url = format!("http://{}/hello", addr);
+async fn test_greet_endpoint() {
+    let response = send(get("/greet/Rustacean")).await;
 
-    let response = client.get(&url).send().await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let greeting: GreetingResponse = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(greeting, GreetingResponse { greeting: "Hello, Rustacean!".to_string() });
+}
+
+#[tokio::test]
+async fn test_echo_json_endpoint() {
+    let payload = EchoPayload { message: "测试 JSON echo".to_string(), count: 123 };
+    let request = Request::post("/echo_json")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .unwrap();
+    let response = send(request).await;
 
     assert_eq!(response.status(), StatusCode::OK);
-    assert!(response.headers().get("content-type").unwrap().to_str()?.contains("application/json"));
+    assert!(content_type(&response).contains("application/json"));
+    let echoed: EchoPayload = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(echoed, payload);
+}
+
+#[tokio::test]
+async fn test_echo_json_rejects_invalid_payload() {
+    // 缺少 count 字段：由 axum 的 Json 提取器拒绝
+    let request = Request::post("/echo_json")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "message": "hi" }).to_string()))
+        .unwrap();
+    let response = send(request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(problem(response).await.detail.contains("missing field `count`"));
+}
+
+#[tokio::test]
+async fn test_echo_json_validates_payload() {
+    let response = send(json_request("POST", "/echo_json", json!({ "message": "", "count": -1 }))).await;
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let problem = problem(response).await;
+    assert_eq!(
+        problem.errors,
+        [field_error("count", "range", "计数不能为负"), field_error("message", "length", "消息不能为空")]
+    );
+}
 
-    let expected_greeting = GreetingResponse { greeting: "Hello, Web from Axum!".to_string() };
-    let actual_greeting: GreetingResponse = response.json().await?;
+#[tokio::test]
+async fn test_echo_json_rejects_malformed_requests() {
+    // JSON 语法错误
+    let request = Request::post("/echo_json")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{\"message\": "))
+        .unwrap();
+    let response = send(request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    problem(response).await;
+
+    // 缺少 Content-Type
+    let request = Request::post("/echo_json").body(Body::from(r#"{"message": "hi", "count": 1}"#)).unwrap();
+    let response = send(request).await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    problem(response).await;
+}
 
-    assert_eq!(actual_greeting, expected_greeting);
+#[tokio::test]
+async fn test_404_not_found() {
+    let response = send(get("/non_existent_path")).await;
 
-    let _ = shutdown_tx.send(());
-    Ok(())
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(problem(response).await.detail, "路径 /non_existent_path 不存在");
 }
 
 #[tokio::test]
-async fn test_greet_endpoint() -> anyhow::Result<()> {
-    let (addr, shutdown_tx) = spawn_test_server().await;
-    let client = Client::new();
-    let name = "Rustacean";
-    let url = format!("http://{}/greet/{}", addr, name);
+async fn test_405_method_not_allowed() {
+    let response = send(Request::delete("/hello").body(Body::empty()).unwrap()).await;
 
-    let response = client.get(&url).send().await?;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()[header::ALLOW], "GET,HEAD");
+    assert_eq!(problem(response).await.detail, "/hello 不支持 DELETE 方法");
+}
 
-    assert_eq!(response.status(), StatusCode::OK);
-    assert!(response.headers().get("content-type").unwrap().to_str()?.contains("application/json"));
+#[tokio::test]
+async fn test_invalid_path_and_query_parameters() {
+    let response = send(get("/items/abc")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    problem(response).await;
+
+    let response = send(get("/items?limit=-1")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    problem(response).await;
+}
+
+#[tokio::test]
+async fn test_serve_until_shutdown() {
+    // 端口 0 表示随机选择可用端口
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(simple_api::serve(listener, test_state().await, Config::default(), async {
+        shutdown_rx.await.ok();
+    }));
+
+    let response = reqwest::get(format!("http://{}/hello", addr)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let greeting: GreetingResponse = response.json().await.unwrap();
+    assert_eq!(greeting.greeting, "Hello, Web from Axum!");
+
+    // 发送关闭信号后 serve 正常返回
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
 
-    let expected_greeting = GreetingResponse { greeting: format!("Hello, {}!", name) };
-    let actual_greeting: GreetingResponse = response.json().await?;
+#[tokio::test]
+async fn test_body_limit() {
+    let config = Config { body_limit: 64, ..Config::default() };
+    let app = app(test_state().await, &config);
+
+    let body = json!({ "message": "x".repeat(100), "count": 1 });
+    let response = app.oneshot(json_request("POST", "/echo_json", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    problem(response).await;
+}
 
-    assert_eq!(actual_greeting, expected_greeting);
+// list 需要一分钟的存储，其他操作直接交给内存数据库
+struct SlowRepository(SqliteItemRepository);
 
-    let _ = shutdown_tx.send(());
-    Ok(())
+#[async_trait::async_trait]
+impl ItemRepository for SlowRepository {
+    async fn list(&self, page: Pagination) -> Result<(Vec<Item>, u64), RepositoryError> {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        self.0.list(page).await
+    }
+    async fn get(&self, id: i64) -> Result<Option<Item>, RepositoryError> {
+        self.0.get(id).await
+    }
+    async fn create(&self, item: NewItem) -> Result<Item, RepositoryError> {
+        self.0.create(item).await
+    }
+    async fn update(&self, id: i64, item: NewItem) -> Result<Option<Item>, RepositoryError> {
+        self.0.update(id, item).await
+    }
+    async fn patch(&self, id: i64, patch: ItemPatch) -> Result<Option<Item>, RepositoryError> {
+        self.0.patch(id, patch).await
+    }
+    async fn delete(&self, id: i64) -> Result<bool, RepositoryError> {
+        self.0.delete(id).await
+    }
 }
 
+// 时间暂停时，所有任务都在等待的话 tokio 直接把时间拨到下一个到期的计时器，测试不需要真的等待。
+// 连接数据库要等待 sqlx 的工作线程，所以连接好之后再暂停时间
 #[tokio::test]
-async fn test_echo_json_endpoint() -> anyhow::Result<()> {
-    let (addr, shutdown_tx) = spawn_test_server().await;
-    let client = Client::new();
-    let url = format!("http://{}/echo_json", addr);
+async fn test_request_timeout() {
+    let config = Config { request_timeout: Duration::from_secs(5), ..Config::default() };
+    let items = SlowRepository(SqliteItemRepository::in_memory().await.unwrap());
+    tokio::time::pause();
+    let app = app(AppState::new(items), &config);
+
+    let response = app.oneshot(get("/items")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+    assert_eq!(problem(response).await.detail, "请求没有在 5 秒内处理完");
+}
 
-    let payload_to_send = EchoPayload {
-        message: "测试 JSON echo".to_string(),
-        count: 123,
-    };
+#[tokio::test]
+async fn test_item_crud() {
+    let app = test_app().await;
+
+    // POST: 201，Location 指向新条目，省略的字段取默认值
+    let response = app.clone().oneshot(json_request("POST", "/items", json!({ "name": "螺丝" }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers()[header::LOCATION].to_str().unwrap().to_string();
+    let created: Item = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(location, format!("/items/{}", created.id));
+    assert_eq!((created.name.as_str(), created.description.as_deref(), created.quantity), ("螺丝", None, 0));
+
+    // GET
+    let response = app.clone().oneshot(get(&location)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let fetched: Item = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(fetched, created);
 
-    let response = client.post(&url)
-        .json(&payload_to_send) // 发送 JSON 请求体
-        .send()
-        .await?;
+    // PUT: 整体替换
+    let body = json!({ "name": "螺母", "description": "M6", "quantity": 10 });
+    let response = app.clone().oneshot(json_request("PUT", &location, body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Item = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!((updated.id, updated.name.as_str(), updated.description.as_deref(), updated.quantity), (created.id, "螺母", Some("M6"), 10));
+    assert_eq!(updated.created_at, created.created_at);
+
+    // DELETE: 204，之后 GET 和再次 DELETE 都是 404
+    let response = app.clone().oneshot(Request::delete(&location).body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.clone().oneshot(get(&location)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(problem(response).await.detail, format!("条目 {} 不存在", created.id));
+    let response = app.clone().oneshot(Request::delete(&location).body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
 
+#[tokio::test]
+async fn test_item_patch() {
+    let app = test_app().await;
+    let item = create(&app, json!({ "name": "垫片", "description": "不锈钢", "quantity": 3 })).await;
+    let uri = format!("/items/{}", item.id);
+
+    // 没有出现的字段保持原值
+    let response = app.clone().oneshot(json_request("PATCH", &uri, json!({ "quantity": 5 }))).await.unwrap();
     assert_eq!(response.status(), StatusCode::OK);
-    assert!(response.headers().get("content-type").unwrap().to_str()?.contains("application/json"));
+    let patched: Item = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!((patched.name.as_str(), patched.description.as_deref(), patched.quantity), ("垫片", Some("不锈钢"), 5));
+
+    // 显式的 null 清除 description
+    let response = app.clone().oneshot(json_request("PATCH", &uri, json!({ "description": null }))).await.unwrap();
+    let patched: Item = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!((patched.description, patched.quantity), (None, 5));
+
+    // name 不能为 null：条目总要有名称。与其他字段的错误一起列出
+    let body = json!({ "name": null, "description": "长".repeat(1001), "quantity": -1 });
+    let response = app.clone().oneshot(json_request("PATCH", &uri, body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let problem: Problem = serde_json::from_str(&body_text(response).await).unwrap();
+    let fields: Vec<_> = problem.errors.iter().map(|error| (error.field.as_str(), error.code.as_str())).collect();
+    assert_eq!(fields, [("description", "length"), ("name", "required"), ("quantity", "range")]);
+    let item: Item = serde_json::from_str(&body_text(app.clone().oneshot(get(&uri)).await.unwrap()).await).unwrap();
+    assert_eq!(item.name, "垫片");
+
+    let response = app.clone().oneshot(json_request("PATCH", "/items/999", json!({ "quantity": 1 }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
 
-    let echoed_payload: EchoPayload = response.json().await?;
-    assert_eq!(echoed_payload, payload_to_send);
+#[tokio::test]
+async fn test_item_pagination() {
+    let app = test_app().await;
+    for i in 0..5 {
+        create(&app, json!({ "name": format!("条目 {}", i) })).await;
+    }
 
-    let _ = shutdown_tx.send(());
-    Ok(())
+    let response = app.clone().oneshot(get("/items?limit=2&offset=1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: ItemPage = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!((page.total, page.limit, page.offset), (5, 2, 1));
+    let names: Vec<_> = page.items.iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(names, ["条目 1", "条目 2"]);
+
+    // 默认每页 20 条，limit 最大为 100
+    let page: ItemPage = serde_json::from_str(&body_text(app.clone().oneshot(get("/items")).await.unwrap()).await).unwrap();
+    assert_eq!((page.items.len(), page.limit, page.offset), (5, 20, 0));
+    let response = app.clone().oneshot(get("/items?limit=1000")).await.unwrap();
+    let page: ItemPage = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(page.limit, 100);
 }
 
 #[tokio::test]
-async fn test_404_not_found() -> anyhow::Result<()> {
-    let (addr, shutdown_tx) = spawn_test_server().await;
-    let client = Client::new();
-    let url = format!("http://{}/non_existent_path", addr);
+async fn test_item_duplicate_name_conflicts() {
+    let app = test_app().await;
+    let first = create(&app, json!({ "name": "螺栓" })).await;
+    let second = create(&app, json!({ "name": "垫圈" })).await;
+
+    let response = app.clone().oneshot(json_request("POST", "/items", json!({ "name": "螺栓" }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert!(problem(response).await.detail.contains("UNIQUE"));
+
+    // 改名为已存在的名称同样冲突
+    let uri = format!("/items/{}", second.id);
+    let response = app.clone().oneshot(json_request("PATCH", &uri, json!({ "name": first.name }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
 
-    let response = client.get(&url).send().await?;
+#[tokio::test]
+async fn test_item_validation() {
+    let app = test_app().await;
+
+    let body = json!({ "name": "", "quantity": -3 });
+    let response = app.clone().oneshot(json_request("POST", "/items", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        problem(response).await.errors,
+        [field_error("name", "length", "长度必须在 1 到 100 之间"), field_error("quantity", "range", "不能小于 0")]
+    );
+
+    // PATCH 只检查出现的字段
+    let item = create(&app, json!({ "name": "弹簧" })).await;
+    let uri = format!("/items/{}", item.id);
+    let body = json!({ "description": "长".repeat(1001) });
+    let response = app.clone().oneshot(json_request("PATCH", &uri, body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem(response).await.errors, [field_error("description", "length", "长度不能大于 1000")]);
+    let response = app.clone().oneshot(json_request("PATCH", &uri, json!({ "quantity": 2 }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
 
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
-    assert!(response.headers().get("content-type").unwrap().to_str()?.contains("text/html"));
-    let body = response.text().await?;
-    assert!(body.contains("404: 页面未找到"));
+// ValidatedJson 适用于任何实现了 Validate 的请求结构体：正则和嵌套结构 (包括列表) 的错误带有字段路径
 
-    let _ = shutdown_tx.send(());
-    Ok(())
+static SKU: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Z]{3}-\d{4}$").unwrap());
+
+#[derive(Deserialize, Validate)]
+struct Order {
+    #[validate(regex(path = *SKU, message = "SKU 格式应为 ABC-1234"))]
+    sku: String,
+    #[validate(nested)]
+    shipping: Address,
+    #[validate(nested)]
+    lines: Vec<OrderLine>,
 }
 
-// 更多测试可以添加，例如：
-// - 测试无效的 JSON payload
-// - 测试不同的 HTTP 方法 (如果路由支持)
-// - 测试请求头
-// - 测试错误处理 (如果 main.rs 中实现了 AppError 并用于 handler)
-//   例如，发送一个空的 message 给 /echo_json (如果 _fallible_handler 被使用)
-//   并期望一个 400 Bad Request 响应。
-//
-// fn create_app_for_test() -> Router { // 辅助函数创建测试用的 App Router
-//     // ... (与 main.rs 或 spawn_test_server 中相同的路由定义)
-//     // 这避免了在多个地方重复路由定义，如果路由逻辑复杂的话
-// }
-//
-// 如果使用 tower::ServiceExt 进行内存测试 (更推荐的方式):
-// use tower::ServiceExt; // for `oneshot` and `ready`
-// use axum::body::Body;
-// use axum::http::{Request, StatusCode as AxumStatusCode};
-// use http_body_util::BodyExt; // for `to_bytes`
-//
-// #[tokio::test]
-// async fn test_root_with_service_ext() {
-//     // let app = create_app_for_test(); // 或者直接从 main.rs 导入 app() 函数
-//     // ... (与 spawn_test_server 中相同的路由定义)
-//     use axum::{routing::get, Router, response::Html, extract::Path, Json};
-//     use serde::{Serialize, Deserialize};
-//     #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-//     struct EchoPayloadTest { message: String, count: i32 }
-//     #[derive(Serialize, Deserialize, Debug, PartialEq)]
-//     struct GreetingResponseTest { greeting: String }
-//     async fn root_handler_service() -> Html<&'static str> { Html("<h1>Welcome</h1>") }
-//     let app = Router::new().route("/", get(root_handler_service));
-
-
-//     let response = app
-//         .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
-//         .await
-//         .unwrap();
-//     assert_eq!(response.status(), AxumStatusCode::OK);
-//     let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
-//     assert!(String::from_utf8_lossy(&body_bytes).contains("<h1>Welcome</h1>"));
-// }
-// 这种方式更快，因为它不涉及实际的网络堆栈，并且更容易控制。
-// 但它可能无法完全模拟真实网络环境中的某些行为。
-// 对于本教程，使用 reqwest 的外部测试方式更直观地展示了 HTTP API 的行为。
+#[derive(Deserialize, Validate)]
+struct Address {
+    #[validate(length(min = 2))]
+    city: String,
+}
+
+#[derive(Deserialize, Validate)]
+struct OrderLine {
+    #[validate(range(min = 1, max = 99))]
+    quantity: u32,
+}
+
+async fn place_order(ValidatedJson(order): ValidatedJson<Order>) -> Json<usize> {
+    Json(order.lines.len())
+}
+
+#[tokio::test]
+async fn test_validated_json_regex_and_nested_rules() {
+    let orders = Router::new().route("/orders", post(place_order));
+
+    let valid = json!({ "sku": "ABC-1234", "shipping": { "city": "上海" }, "lines": [{ "quantity": 1 }] });
+    let response = orders.clone().oneshot(json_request("POST", "/orders", valid)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let invalid = json!({
+        "sku": "abc",
+        "shipping": { "city": "X" },
+        "lines": [{ "quantity": 1 }, { "quantity": 100 }],
+    });
+    let response = orders.oneshot(json_request("POST", "/orders", invalid)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let problem = problem(response).await;
+    assert_eq!(problem.detail, "请求内容未通过校验");
+    assert_eq!(
+        problem.errors,
+        [
+            field_error("lines[1].quantity", "range", "必须在 1 到 99 之间"),
+            field_error("shipping.city", "length", "长度不能小于 2"),
+            field_error("sku", "regex", "SKU 格式应为 ABC-1234"),
+        ]
+    );
+}
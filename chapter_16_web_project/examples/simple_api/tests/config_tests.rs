@@ -0,0 +1,136 @@
+// tests/config_tests.rs
+//
+// 设置的合并和检查。配置文件的内容和环境变量直接传给 `Config::from_sources`，
+// 不读取真实的文件和环境变量，测试之间互不影响。
+
+use clap::Parser;
+use simple_api::config::{Cli, Config, LogLevel};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn cli(args: &[&str]) -> Cli {
+    Cli::try_parse_from(std::iter::once("simple_api").chain(args.iter().copied())).unwrap()
+}
+
+fn file(text: &str) -> Option<(PathBuf, String)> {
+    Some((PathBuf::from("simple_api.toml"), text.to_string()))
+}
+
+fn load(args: &[&str], file: Option<(PathBuf, String)>, env: &[(&str, &str)]) -> Result<Config, String> {
+    let env: HashMap<String, String> = env.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+    Config::from_sources(&cli(args), file, |name| env.get(name).cloned())
+}
+
+#[test]
+fn test_defaults() {
+    let config = load(&[], None, &[]).unwrap();
+
+    assert_eq!(config, Config::default());
+    assert_eq!(config.addr(), SocketAddr::from(([127, 0, 0, 1], 3000)));
+    assert_eq!(config.log_level, LogLevel::Info);
+}
+
+#[test]
+fn test_all_settings_from_file() {
+    let text = r#"
+        host = "0.0.0.0"
+        port = 8080
+        log_level = "debug"
+        body_limit = 1024
+        request_timeout = 5
+        shutdown_timeout = 0
+        database_url = "sqlite::memory:"
+    "#;
+    let config = load(&[], file(text), &[]).unwrap();
+
+    let expected = Config {
+        host: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        port: 8080,
+        log_level: LogLevel::Debug,
+        body_limit: 1024,
+        request_timeout: Duration::from_secs(5),
+        shutdown_timeout: Duration::ZERO,
+        database_url: "sqlite::memory:".to_string(),
+    };
+    assert_eq!(config, expected);
+}
+
+#[test]
+fn test_precedence() {
+    // 命令行 > 环境变量 > 配置文件 > 默认值
+    let text = "port = 8080\nlog_level = \"debug\"\nbody_limit = 1024";
+    let env = [("SIMPLE_API_PORT", "9090"), ("SIMPLE_API_LOG_LEVEL", "WARN")];
+    let config = load(&["--port", "7070"], file(text), &env).unwrap();
+
+    assert_eq!(config.port, 7070);
+    assert_eq!(config.log_level, LogLevel::Warn);
+    assert_eq!(config.body_limit, 1024);
+    assert_eq!(config.request_timeout, Config::default().request_timeout);
+}
+
+#[test]
+fn test_bind_sets_host_and_port() {
+    let config = load(&[], file("bind = \"[::1]:4000\""), &[]).unwrap();
+    assert_eq!(config.addr(), "[::1]:4000".parse().unwrap());
+
+    // 更高优先级的来源可以只覆盖其中一项
+    let config = load(&["--port", "5000"], file("bind = \"0.0.0.0:4000\""), &[]).unwrap();
+    assert_eq!(config.addr(), SocketAddr::from(([0, 0, 0, 0], 5000)));
+}
+
+#[test]
+fn test_bind_and_host_port_across_sources() {
+    // 不同来源中的 bind 与 host、port 不冲突，按优先级逐项覆盖
+    let config = load(&[], file("bind = \"0.0.0.0:4000\""), &[("SIMPLE_API_HOST", "::1")]).unwrap();
+    assert_eq!(config.addr(), "[::1]:4000".parse().unwrap());
+
+    let config = load(&[], file("host = \"0.0.0.0\"\nport = 4000"), &[("SIMPLE_API_BIND", "127.0.0.2:5000")]).unwrap();
+    assert_eq!(config.addr(), SocketAddr::from(([127, 0, 0, 2], 5000)));
+
+    let config = load(&["--bind", "127.0.0.3:6000"], None, &[("SIMPLE_API_HOST", "::"), ("SIMPLE_API_PORT", "5000")]).unwrap();
+    assert_eq!(config.addr(), SocketAddr::from(([127, 0, 0, 3], 6000)));
+}
+
+#[test]
+fn test_conflicting_settings_are_rejected() {
+    let error = load(&[], file("bind = \"0.0.0.0:4000\"\nport = 5000"), &[]).unwrap_err();
+    assert_eq!(error, "配置文件 simple_api.toml: bind 不能与 host 或 port 同时设置");
+
+    let error = load(&[], None, &[("SIMPLE_API_BIND", "0.0.0.0:4000"), ("SIMPLE_API_HOST", "::")]).unwrap_err();
+    assert_eq!(error, "环境变量: bind 不能与 host 或 port 同时设置");
+
+    // 命令行上由 clap 检查
+    let error = Cli::try_parse_from(["simple_api", "--bind", "0.0.0.0:4000", "--port", "5000"]).unwrap_err();
+    assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn test_invalid_settings_are_rejected() {
+    let error = load(&[], file("request_timeout = 0"), &[]).unwrap_err();
+    assert_eq!(error, "配置文件 simple_api.toml: request_timeout 必须大于 0");
+
+    let error = load(&["--body-limit", "0"], None, &[]).unwrap_err();
+    assert_eq!(error, "命令行: body_limit 必须大于 0");
+
+    let error = load(&[], None, &[("SIMPLE_API_PORT", "70000")]).unwrap_err();
+    assert_eq!(error, "环境变量 SIMPLE_API_PORT 有误: number too large to fit in target type ('70000')");
+
+    let error = load(&[], None, &[("SIMPLE_API_LOG_LEVEL", "verbose")]).unwrap_err();
+    assert!(error.starts_with("环境变量 SIMPLE_API_LOG_LEVEL 有误"), "{}", error);
+
+    // 被更高优先级的来源覆盖的设置有误时同样报告
+    let error = load(&["--host", "::"], file("host = \"localhost\""), &[]).unwrap_err();
+    assert!(error.starts_with("配置文件 simple_api.toml 有误"), "{}", error);
+
+    // 未知的设置名多半是拼写错误
+    let error = load(&[], file("prot = 8080"), &[]).unwrap_err();
+    assert!(error.contains("unknown field `prot`"), "{}", error);
+}
+
+#[test]
+fn test_missing_config_file_is_an_error() {
+    let error = Config::load(&cli(&["--config", "/nonexistent/simple_api.toml"])).unwrap_err();
+    assert!(error.starts_with("无法读取配置文件 /nonexistent/simple_api.toml"), "{}", error);
+}